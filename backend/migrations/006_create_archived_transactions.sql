@@ -0,0 +1,15 @@
+-- create archived transactions table (same shape as transactions, plus when it was archived)
+CREATE TABLE IF NOT EXISTS archived_transactions (
+    id UUID PRIMARY KEY,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE, -- links to users table
+    amount NUMERIC(15, 2) NOT NULL,
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('income', 'expense')),
+    category TEXT,
+    description TEXT,
+    date DATE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- create index for browsing a user's archive by date
+CREATE INDEX IF NOT EXISTS idx_archived_transactions_user_date ON archived_transactions(user_id, date);
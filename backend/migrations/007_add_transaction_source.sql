@@ -0,0 +1,5 @@
+-- add an optional income source (e.g. "Salary", "Freelance") to transactions, separate from category
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS source TEXT;
+
+-- keep the archive the same shape as the main table
+ALTER TABLE archived_transactions ADD COLUMN IF NOT EXISTS source TEXT;
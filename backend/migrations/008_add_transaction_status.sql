@@ -0,0 +1,7 @@
+-- add a clearing status to transactions (pending until it clears at the bank)
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS status VARCHAR(10) NOT NULL DEFAULT 'cleared' CHECK (status IN ('pending', 'cleared'));
+
+-- keep the archive the same shape as the main table
+ALTER TABLE archived_transactions
+    ADD COLUMN IF NOT EXISTS status VARCHAR(10) NOT NULL DEFAULT 'cleared' CHECK (status IN ('pending', 'cleared'));
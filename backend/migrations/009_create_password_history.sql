@@ -0,0 +1,10 @@
+-- create password history table (previous password hashes, so they can't be reused)
+CREATE TABLE IF NOT EXISTS password_history (
+    id BIGSERIAL PRIMARY KEY, -- increasing id gives us a stable newest-first order
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- links to users table
+    password_hash VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- create index on user_id for faster lookups
+CREATE INDEX IF NOT EXISTS idx_password_history_user_id ON password_history(user_id);
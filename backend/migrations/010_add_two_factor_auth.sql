@@ -0,0 +1,13 @@
+-- add the TOTP secret to users (NULL means two-factor auth is turned off)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret BYTEA;
+
+-- create backup codes table (one-time codes for when the authenticator isn't available)
+CREATE TABLE IF NOT EXISTS totp_backup_codes (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- links to users table
+    code_hash VARCHAR(255) NOT NULL, -- argon2 hash, the plain code is only shown once
+    used_at TIMESTAMPTZ -- set once the code has been spent
+);
+
+-- create index on user_id for faster lookups
+CREATE INDEX IF NOT EXISTS idx_totp_backup_codes_user_id ON totp_backup_codes(user_id);
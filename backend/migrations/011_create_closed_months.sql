@@ -0,0 +1,7 @@
+-- create closed months table (months whose transactions are locked against changes)
+CREATE TABLE IF NOT EXISTS closed_months (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- links to users table
+    month DATE NOT NULL, -- store as first day of the month (e.g., 2026-01-01)
+    closed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, month)
+);
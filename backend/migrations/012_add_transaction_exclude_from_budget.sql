@@ -0,0 +1,5 @@
+-- let transactions opt out of budget tracking (e.g. a work expense that gets reimbursed)
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS exclude_from_budget BOOLEAN NOT NULL DEFAULT false;
+
+-- keep the archive the same shape as the main table
+ALTER TABLE archived_transactions ADD COLUMN IF NOT EXISTS exclude_from_budget BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,7 @@
+-- optional free-text merchant and location on transactions, for "where does my money go" breakdowns
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS merchant TEXT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS location TEXT;
+
+-- keep the archive the same shape as the main table
+ALTER TABLE archived_transactions ADD COLUMN IF NOT EXISTS merchant TEXT;
+ALTER TABLE archived_transactions ADD COLUMN IF NOT EXISTS location TEXT;
@@ -0,0 +1,7 @@
+-- how many decimal places a user's transaction amounts may have (2 for most currencies, up to 8 for crypto/stock cost bases)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS amount_scale SMALLINT NOT NULL DEFAULT 2;
+
+-- drop the fixed 2 place scale on transaction amounts, so each amount keeps the places it was entered with
+-- (NUMERIC(p, 8) would pad every existing 12.50 out to 12.50000000); the app enforces the bounds instead
+ALTER TABLE transactions ALTER COLUMN amount TYPE NUMERIC;
+ALTER TABLE archived_transactions ALTER COLUMN amount TYPE NUMERIC;
@@ -0,0 +1,2 @@
+-- each user's IANA timezone (e.g. 'Pacific/Auckland'), so "today" and "this month" roll over at their midnight
+ALTER TABLE users ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'UTC';
@@ -0,0 +1,12 @@
+-- create category rules table (a transaction whose description contains match_contains gets the rule's category)
+CREATE TABLE IF NOT EXISTS category_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- links to users table
+    match_contains TEXT NOT NULL, -- matched case-insensitively anywhere in the description
+    category TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0, -- lower runs first, and the first matching rule wins
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- create index on user_id for faster lookups
+CREATE INDEX IF NOT EXISTS idx_category_rules_user_id ON category_rules(user_id);
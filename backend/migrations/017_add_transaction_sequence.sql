@@ -0,0 +1,51 @@
+-- per-user sequential transaction numbers, for human-friendly references like TXN-00042
+-- each user keeps a counter, bumped by a trigger on every insert; the UPDATE locks the user's row, so concurrent
+-- inserts for one user queue up behind each other and can never be handed the same number
+ALTER TABLE users ADD COLUMN IF NOT EXISTS transaction_seq BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS seq BIGINT;
+ALTER TABLE archived_transactions ADD COLUMN IF NOT EXISTS seq BIGINT;
+
+-- number existing transactions (live and archived together) in the order they were created
+WITH numbered AS (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY created_at, id) AS seq
+    FROM (
+        SELECT id, user_id, created_at FROM transactions
+        UNION ALL
+        SELECT id, user_id, created_at FROM archived_transactions
+    ) all_transactions
+)
+UPDATE transactions t SET seq = numbered.seq FROM numbered WHERE t.id = numbered.id AND t.seq IS NULL;
+
+WITH numbered AS (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY created_at, id) AS seq
+    FROM (
+        SELECT id, user_id, created_at FROM transactions
+        UNION ALL
+        SELECT id, user_id, created_at FROM archived_transactions
+    ) all_transactions
+)
+UPDATE archived_transactions a SET seq = numbered.seq FROM numbered WHERE a.id = numbered.id AND a.seq IS NULL;
+
+UPDATE users u SET transaction_seq = COALESCE((
+    SELECT MAX(seq) FROM (
+        SELECT seq FROM transactions WHERE user_id = u.id
+        UNION ALL
+        SELECT seq FROM archived_transactions WHERE user_id = u.id
+    ) all_seqs
+), 0);
+
+CREATE OR REPLACE FUNCTION assign_transaction_seq() RETURNS trigger AS $$
+BEGIN
+    UPDATE users SET transaction_seq = transaction_seq + 1 WHERE id = NEW.user_id
+    RETURNING transaction_seq INTO NEW.seq;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS transactions_assign_seq ON transactions;
+CREATE TRIGGER transactions_assign_seq
+BEFORE INSERT ON transactions
+FOR EACH ROW EXECUTE FUNCTION assign_transaction_seq();
+
+ALTER TABLE transactions ALTER COLUMN seq SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transactions_user_seq ON transactions(user_id, seq);
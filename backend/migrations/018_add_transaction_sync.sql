@@ -0,0 +1,44 @@
+-- incremental sync: when each transaction last changed, and a tombstone for each one removed from the live table
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE archived_transactions ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+-- existing rows last changed no later than when they were created, as far as anyone can tell
+UPDATE transactions SET updated_at = created_at;
+UPDATE archived_transactions SET updated_at = created_at;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_user_updated_at ON transactions(user_id, updated_at);
+
+-- bump updated_at on every change, so no UPDATE statement can forget to
+CREATE OR REPLACE FUNCTION touch_transaction_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at = CURRENT_TIMESTAMP;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS transactions_touch_updated_at ON transactions;
+CREATE TRIGGER transactions_touch_updated_at
+BEFORE UPDATE ON transactions
+FOR EACH ROW EXECUTE FUNCTION touch_transaction_updated_at();
+
+-- transactions deleted (or moved to the archive) are remembered here, so syncing clients can drop them too
+CREATE TABLE IF NOT EXISTS transaction_tombstones (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL, -- no foreign key: the tombstones are written while a deleted user's transactions cascade away
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_tombstones_user_deleted_at ON transaction_tombstones(user_id, deleted_at);
+
+CREATE OR REPLACE FUNCTION record_transaction_tombstone() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO transaction_tombstones (id, user_id) VALUES (OLD.id, OLD.user_id)
+    ON CONFLICT (id) DO UPDATE SET deleted_at = EXCLUDED.deleted_at;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS transactions_record_tombstone ON transactions;
+CREATE TRIGGER transactions_record_tombstone
+AFTER DELETE ON transactions
+FOR EACH ROW EXECUTE FUNCTION record_transaction_tombstone();
@@ -0,0 +1,14 @@
+-- optional spending limits on top of budgets: a soft "warn at" threshold and a hard "cap at" one
+-- per category, they sit on that month's budget
+ALTER TABLE budgets ADD COLUMN IF NOT EXISTS warn_at NUMERIC(15, 2);
+ALTER TABLE budgets ADD COLUMN IF NOT EXISTS cap_at NUMERIC(15, 2);
+
+-- and across all categories, one row per month
+CREATE TABLE IF NOT EXISTS monthly_limits (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- links to users table
+    month DATE NOT NULL, -- first day of the month
+    warn_at NUMERIC(15, 2),
+    cap_at NUMERIC(15, 2),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, month)
+);
@@ -0,0 +1,28 @@
+-- create households table, for budgets shared between several users
+CREATE TABLE IF NOT EXISTS households (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- create household members table (a user can belong to any number of households)
+CREATE TABLE IF NOT EXISTS household_members (
+    household_id UUID NOT NULL REFERENCES households(id) ON DELETE CASCADE, -- links to households table
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- links to users table
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (household_id, user_id)
+);
+
+-- create index on user_id for looking up a user's households
+CREATE INDEX IF NOT EXISTS idx_household_members_user_id ON household_members(user_id);
+
+-- create household budgets table (one budget per household, month and category, spent against by every member)
+CREATE TABLE IF NOT EXISTS household_budgets (
+    household_id UUID NOT NULL REFERENCES households(id) ON DELETE CASCADE, -- links to households table
+    month DATE NOT NULL, -- first day of the month
+    category TEXT NOT NULL,
+    amount NUMERIC(15, 2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (household_id, month, category)
+);
@@ -0,0 +1,6 @@
+-- each user's notification preferences, for the scheduled monthly summary and budget alert emails
+-- the send day stops at 28 so every month has it
+ALTER TABLE users ADD COLUMN IF NOT EXISTS monthly_summary_enabled BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS budget_alerts_enabled BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS summary_send_day SMALLINT NOT NULL DEFAULT 1
+    CHECK (summary_send_day BETWEEN 1 AND 28);
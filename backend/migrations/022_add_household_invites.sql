@@ -0,0 +1,12 @@
+-- create household invites table: a user is only added to a household once they accept its invite,
+-- since members can see each other's spending through the household
+CREATE TABLE IF NOT EXISTS household_invites (
+    household_id UUID NOT NULL REFERENCES households(id) ON DELETE CASCADE, -- links to households table
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- the invited user
+    invited_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- the member who sent the invite
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (household_id, user_id)
+);
+
+-- create index on user_id for looking up a user's pending invites
+CREATE INDEX IF NOT EXISTS idx_household_invites_user_id ON household_invites(user_id);
@@ -0,0 +1,690 @@
+use sqlx::Row;
+use sqlx::types::Decimal;
+
+use crate::extract::{JsonBody, SignedAmounts};
+use crate::handlers::{db_error, month_days_elapsed, month_start_of, next_month_start_of, transaction_from_row, user_today};
+use crate::households::scope_user_ids;
+use crate::models::*;
+use crate::notes::open_note;
+
+/* analytics */
+
+// planned transactions dated after the user's today haven't happened yet, so every analytic here leaves them out
+
+// route for getting total income broken down by source, optionally within a date range
+pub(crate) async fn get_income_by_source(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<DateRangeQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<IncomeBySource>>, (axum::http::StatusCode, String)> {
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    // sum income per source, with income that has no source grouped together under NULL
+    let rows = sqlx::query(
+        "SELECT source, SUM(amount) AS total
+        FROM transactions
+        WHERE user_id = $1
+        AND kind = 'income'
+        AND ($2::date IS NULL OR date >= $2)
+        AND ($3::date IS NULL OR date <= $3)
+        AND (status = 'cleared' OR $4)
+        AND date <= $5
+        GROUP BY source
+        ORDER BY total DESC, source ASC"
+    )
+    .bind(auth.user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<IncomeBySource> = rows
+        .into_iter()
+        .map(|row| IncomeBySource {
+            source: row.get("source"),
+            total: row.get("total"),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+
+// route for getting net cashflow per day or month, with a running balance for drawing a balance-over-time line
+pub(crate) async fn get_cashflow(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<CashflowQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<CashflowPoint>>, (axum::http::StatusCode, String)> {
+
+    let interval = match query.interval {
+        Some(CashflowInterval::Day) => "day",
+        Some(CashflowInterval::Month) | None => "month",
+    };
+
+    let user_ids = scope_user_ids(&state.pool, auth.user_id, query.household_id).await?;
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    // net per period first, then a window function over the periods for the running balance
+    let rows = sqlx::query(
+        "SELECT period, net, $6::numeric + SUM(net) OVER (ORDER BY period) AS cumulative
+        FROM (
+            SELECT date_trunc($5::text, date::timestamp)::date AS period,
+                SUM(CASE WHEN kind = 'income' THEN amount ELSE -amount END) AS net
+            FROM transactions
+            WHERE user_id = ANY($1)
+            AND ($2::date IS NULL OR date >= $2)
+            AND ($3::date IS NULL OR date <= $3)
+            AND (status = 'cleared' OR $4)
+            AND date <= $7
+            GROUP BY 1
+        ) AS periods
+        ORDER BY period"
+    )
+    .bind(&user_ids)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(interval)
+    .bind(query.starting_balance.unwrap_or(Decimal::ZERO))
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<CashflowPoint> = rows
+        .into_iter()
+        .map(|row| CashflowPoint {
+            period: row.get("period"),
+            net: row.get("net"),
+            cumulative: row.get("cumulative"),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+
+// route for getting total income and expense, what was saved and the savings rate, optionally within a date range
+pub(crate) async fn get_savings_rate(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<DateRangeQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<SavingsRate>, (axum::http::StatusCode, String)> {
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    let row = sqlx::query(
+        "SELECT
+            COALESCE(SUM(amount) FILTER (WHERE kind = 'income'), 0) AS income,
+            COALESCE(SUM(amount) FILTER (WHERE kind = 'expense'), 0) AS expense
+        FROM transactions
+        WHERE user_id = $1
+        AND ($2::date IS NULL OR date >= $2)
+        AND ($3::date IS NULL OR date <= $3)
+        AND (status = 'cleared' OR $4)
+        AND date <= $5"
+    )
+    .bind(auth.user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let income: Decimal = row.get("income");
+    let expense: Decimal = row.get("expense");
+    let saved = income - expense;
+
+    // no income means there's no rate to speak of, rather than a division by zero
+    let rate = (!income.is_zero()).then(|| (saved * Decimal::from(100) / income).round_dp(1));
+
+    Ok(axum::Json(SavingsRate { income, expense, saved, rate }))
+}
+
+
+// route for finding expenses with no category (NULL or blank), so they can be tracked down and fixed
+pub(crate) async fn get_uncategorized_spending(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<UncategorizedQuery>,
+    signed: SignedAmounts,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<UncategorizedSpending>, (axum::http::StatusCode, String)> {
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    // the totals cover every matching expense, not just the requested page
+    let totals = sqlx::query(
+        "SELECT COALESCE(SUM(amount), 0) AS total, COUNT(*) AS count
+        FROM transactions
+        WHERE user_id = $1
+        AND kind = 'expense'
+        AND (category IS NULL OR TRIM(category) = '')
+        AND ($2::date IS NULL OR date >= $2)
+        AND ($3::date IS NULL OR date <= $3)
+        AND (status = 'cleared' OR $4)
+        AND date <= $5"
+    )
+    .bind(auth.user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let rows = sqlx::query(
+        "SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at
+        FROM transactions
+        WHERE user_id = $1
+        AND kind = 'expense'
+        AND (category IS NULL OR TRIM(category) = '')
+        AND ($2::date IS NULL OR date >= $2)
+        AND ($3::date IS NULL OR date <= $3)
+        AND (status = 'cleared' OR $4)
+        AND date <= $7
+        ORDER BY date DESC, id DESC
+        LIMIT $5 OFFSET $6"
+    )
+    .bind(auth.user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(limit)
+    .bind(offset)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let mut transactions: Vec<Transaction> = rows.iter().map(|row| transaction_from_row(row, auth.user_id, &state.config)).collect();
+    signed.apply_all(&mut transactions);
+
+    Ok(axum::Json(UncategorizedSpending {
+        total: totals.get("total"),
+        count: totals.get("count"),
+        transactions,
+    }))
+}
+
+// route for getting the user's biggest transactions of one kind in a date range, largest first
+pub(crate) async fn get_largest_transactions(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<LargestTransactionsQuery>,
+    signed: SignedAmounts,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<Transaction>>, (axum::http::StatusCode, String)> {
+
+    let kind = match query.kind {
+        Some(TransactionKind::Income) => "income",
+        Some(TransactionKind::Expense) | None => "expense",
+    };
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    let rows = sqlx::query(
+        "SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at
+        FROM transactions
+        WHERE user_id = $1
+        AND kind = $2
+        AND ($3::date IS NULL OR date >= $3)
+        AND ($4::date IS NULL OR date <= $4)
+        AND (status = 'cleared' OR $5)
+        AND date <= $7
+        ORDER BY amount DESC, date DESC, id DESC
+        LIMIT $6"
+    )
+    .bind(auth.user_id)
+    .bind(kind)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(limit)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let mut result: Vec<Transaction> = rows
+        .iter()
+        .map(|row| transaction_from_row(row, auth.user_id, &state.config))
+        .collect();
+    signed.apply_all(&mut result);
+
+    Ok(axum::Json(result))
+}
+
+// route for projecting end-of-month spend from the month's spend rate so far, per category and overall
+pub(crate) async fn get_spending_forecast(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<SpendingForecast>, (axum::http::StatusCode, String)> {
+
+    // default to the current month, and treat any date as the month containing it
+    let today = user_today(&state.pool, auth.user_id).await?;
+    let month_start = month_start_of(query.month.unwrap_or(today));
+    let next_month_start = next_month_start_of(month_start);
+
+    // work out how far through the month we are
+    let (days_elapsed, days_in_month) = month_days_elapsed(month_start, today);
+
+    // spend per category so far (planned transactions dated after today aren't spent yet), lined up with that month's budgets (either side may be missing)
+    let rows = sqlx::query(
+        "SELECT
+            COALESCE(s.category, b.category) AS category,
+            COALESCE(s.spent, 0)::numeric AS spent,
+            b.amount AS budget
+        FROM (
+            SELECT category, SUM(amount) AS spent
+            FROM transactions
+            WHERE user_id = $1
+            AND kind = 'expense'
+            AND date >= $2
+            AND date < $3
+            AND (status = 'cleared' OR $4)
+            AND NOT exclude_from_budget
+            AND date <= $5
+            GROUP BY category
+        ) s
+        FULL OUTER JOIN (
+            SELECT category, amount
+            FROM budgets
+            WHERE user_id = $1 AND month = $2
+        ) b ON b.category = s.category
+        ORDER BY category ASC NULLS LAST"
+    )
+    .bind(auth.user_id)
+    .bind(month_start)
+    .bind(next_month_start)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let categories: Vec<ForecastPoint> = rows
+        .into_iter()
+        .map(|row| {
+            let spent: Decimal = row.get("spent");
+            let budget: Option<Decimal> = row.get("budget");
+            let projected = project_spend(spent, days_elapsed, days_in_month);
+
+            ForecastPoint {
+                category: row.get("category"),
+                spent,
+                projected,
+                budget,
+                over_budget: budget.is_some_and(|b| projected > b),
+            }
+        })
+        .collect();
+
+    // overall totals across every category
+    let spent: Decimal = categories.iter().map(|c| c.spent).sum();
+    let budget: Decimal = categories.iter().filter_map(|c| c.budget).sum();
+    let projected = project_spend(spent, days_elapsed, days_in_month);
+
+    Ok(axum::Json(SpendingForecast {
+        month: month_start,
+        days_elapsed,
+        days_in_month,
+        categories,
+        total: ForecastTotal {
+            spent,
+            projected,
+            budget,
+            over_budget: budget > Decimal::ZERO && projected > budget,
+        },
+    }))
+}
+
+/* helper functions */
+
+// helper function for a linear end-of-month projection: spent / days_elapsed * days_in_month
+fn project_spend(spent: Decimal, days_elapsed: i64, days_in_month: i64) -> Decimal {
+    // nothing can be projected before the month starts, and a finished month is just what was spent
+    if days_elapsed == 0 {
+        return Decimal::ZERO;
+    }
+    if days_elapsed >= days_in_month {
+        return spent;
+    }
+
+    (spent / Decimal::from(days_elapsed) * Decimal::from(days_in_month)).round_dp(2)
+}
+
+
+// route for comparing spending per category between two periods (e.g. this month vs last month)
+// categories only spent on in one of the periods show up with zero for the other
+pub(crate) async fn get_spending_comparison(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<CompareQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<ComparisonRow>>, (axum::http::StatusCode, String)> {
+
+    if query.period_a_from > query.period_a_to || query.period_b_from > query.period_b_to {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "each period's from date must not be after its to date".to_string()));
+    }
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    // one pass over both periods, with each total filtered to its own range (the periods may overlap)
+    let rows = sqlx::query(
+        "SELECT
+            category,
+            COALESCE(SUM(amount) FILTER (WHERE date BETWEEN $2 AND $3), 0) AS period_a,
+            COALESCE(SUM(amount) FILTER (WHERE date BETWEEN $4 AND $5), 0) AS period_b
+        FROM transactions
+        WHERE user_id = $1
+        AND kind = 'expense'
+        AND (date BETWEEN $2 AND $3 OR date BETWEEN $4 AND $5)
+        AND (status = 'cleared' OR $6)
+        AND date <= $7
+        GROUP BY category
+        ORDER BY category ASC NULLS LAST"
+    )
+    .bind(auth.user_id)
+    .bind(query.period_a_from)
+    .bind(query.period_a_to)
+    .bind(query.period_b_from)
+    .bind(query.period_b_to)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<ComparisonRow> = rows
+        .into_iter()
+        .map(|row| {
+            let period_a: Decimal = row.get("period_a");
+            let period_b: Decimal = row.get("period_b");
+            let delta = period_b - period_a;
+
+            ComparisonRow {
+                category: row.get("category"),
+                period_a,
+                period_b,
+                delta,
+                // rescale rather than round_dp so the percentage always has exactly two places (e.g. "-100.00")
+                pct_change: (!period_a.is_zero()).then(|| {
+                    let mut pct = delta / period_a * Decimal::ONE_HUNDRED;
+                    pct.rescale(2);
+                    pct
+                }),
+            }
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+// route for flagging expenses that are outliers for their category, optionally within a date range
+// each expense is compared against the other expenses in its category (so a big outlier can't drag its own baseline up),
+// and flagged when it's more than ANOMALY_Z_SCORE standard deviations above their mean. categories with fewer than
+// MIN_ANOMALY_SAMPLE_SIZE other expenses to compare against are skipped
+pub(crate) async fn get_anomalies(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<DateRangeQuery>,
+    signed: SignedAmounts,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<Anomaly>>, (axum::http::StatusCode, String)> {
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    // the leave-one-out mean and sample stddev come from the category's count, sum and sum of squares minus the row itself
+    let rows = sqlx::query(
+        "WITH spend AS (
+            SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at,
+                COUNT(*) OVER w - 1 AS others,
+                SUM(amount) OVER w - amount AS others_sum,
+                SUM(amount * amount) OVER w - amount * amount AS others_sum_sq
+            FROM transactions
+            WHERE user_id = $1
+            AND kind = 'expense'
+            AND category IS NOT NULL AND TRIM(category) <> ''
+            AND ($2::date IS NULL OR date >= $2)
+            AND ($3::date IS NULL OR date <= $3)
+            AND (status = 'cleared' OR $4)
+            AND date <= $7
+            WINDOW w AS (PARTITION BY category)
+        ),
+        baselines AS (
+            SELECT *,
+                others_sum / others AS category_mean,
+                sqrt(GREATEST((others_sum_sq - others_sum * others_sum / others) / (others - 1), 0)) AS category_stddev
+            FROM spend
+            WHERE others >= $5
+        ),
+        scored AS (
+            SELECT *, (amount - category_mean) / NULLIF(category_stddev, 0) AS z_score
+            FROM baselines
+        )
+        SELECT * FROM scored
+        WHERE z_score > $6
+        ORDER BY z_score DESC, date DESC, id DESC"
+    )
+    .bind(auth.user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(MIN_ANOMALY_SAMPLE_SIZE)
+    .bind(ANOMALY_Z_SCORE)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<Anomaly> = rows
+        .iter()
+        .map(|row| {
+            let category_mean: Decimal = row.get("category_mean");
+            let category_stddev: Decimal = row.get("category_stddev");
+            let z_score: Decimal = row.get("z_score");
+            let mut transaction = transaction_from_row(row, auth.user_id, &state.config);
+            signed.apply(&mut transaction);
+            Anomaly {
+                transaction,
+                category_mean: category_mean.round_dp(2),
+                category_stddev: category_stddev.round_dp(2),
+                z_score: z_score.round_dp(2),
+            }
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+// route for getting total spending per merchant, biggest first, optionally within a date range
+// (expenses without a merchant are left out, since there's nothing to group them by)
+pub(crate) async fn get_spending_by_merchant(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<DateRangeQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<MerchantSpending>>, (axum::http::StatusCode, String)> {
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    let rows = sqlx::query(
+        "SELECT merchant, SUM(amount) AS total, COUNT(*) AS count
+        FROM transactions
+        WHERE user_id = $1
+        AND kind = 'expense'
+        AND merchant IS NOT NULL AND TRIM(merchant) <> ''
+        AND ($2::date IS NULL OR date >= $2)
+        AND ($3::date IS NULL OR date <= $3)
+        AND (status = 'cleared' OR $4)
+        AND date <= $5
+        GROUP BY merchant
+        ORDER BY total DESC, merchant ASC"
+    )
+    .bind(auth.user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    // encrypted merchants each have their own nonce, so the same merchant can come back as several groups;
+    // merge them by their decrypted name (plaintext merchants just pass through)
+    let mut merged: std::collections::HashMap<String, MerchantSpending> = std::collections::HashMap::new();
+    for row in rows {
+        let merchant = open_note(&state.config, auth.user_id, row.get("merchant")).unwrap_or_default();
+        if merchant.trim().is_empty() {
+            continue;
+        }
+        let total: Decimal = row.get("total");
+        let count: i64 = row.get("count");
+
+        let entry = merged.entry(merchant.clone()).or_insert(MerchantSpending { merchant, total: Decimal::ZERO, count: 0 });
+        entry.total += total;
+        entry.count += count;
+    }
+
+    let mut result: Vec<MerchantSpending> = merged.into_values().collect();
+    result.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.merchant.cmp(&b.merchant)));
+
+    Ok(axum::Json(result))
+}
+
+// route for getting total spending per day of the week, optionally within a date range
+// every day is included (zero when nothing was spent) and the list starts on the configured first day of the week
+pub(crate) async fn get_spending_by_weekday(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<DateRangeQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<WeekdayTotal>>, (axum::http::StatusCode, String)> {
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+
+    // ISODOW numbers the days 1 (Monday) to 7 (Sunday), the same as chrono's number_from_monday
+    let rows = sqlx::query(
+        "SELECT EXTRACT(ISODOW FROM date)::int AS weekday, SUM(amount) AS total
+        FROM transactions
+        WHERE user_id = $1
+        AND kind = 'expense'
+        AND ($2::date IS NULL OR date >= $2)
+        AND ($3::date IS NULL OR date <= $3)
+        AND (status = 'cleared' OR $4)
+        AND date <= $5
+        GROUP BY 1"
+    )
+    .bind(auth.user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let totals: std::collections::HashMap<i32, Decimal> = rows
+        .into_iter()
+        .map(|row| (row.get("weekday"), row.get("total")))
+        .collect();
+
+    let result: Vec<WeekdayTotal> = std::iter::successors(Some(state.config.week_start), |day| Some(day.succ()))
+        .take(7)
+        .map(|weekday| WeekdayTotal {
+            weekday,
+            total: totals.get(&(weekday.number_from_monday() as i32)).copied().unwrap_or(Decimal::ZERO),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+
+// route for running several analytics in one call, concurrently (`?signed=true` on the batch applies to all of them)
+// each result carries its own status, so one bad request in the batch doesn't fail the others
+pub(crate) async fn run_analytics_batch(
+    auth: AuthenticatedUser,
+    signed: SignedAmounts,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<AnalyticsBatchRequest>
+) -> Result<axum::Json<std::collections::BTreeMap<String, AnalyticsBatchResult>>, (axum::http::StatusCode, String)> {
+
+    if req.requests.len() > MAX_ANALYTICS_PER_BATCH {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            format!("At most {} analytics can be requested at once", MAX_ANALYTICS_PER_BATCH),
+        ));
+    }
+
+    // ids name the results, so they have to be unique
+    let mut ids = std::collections::HashSet::new();
+    if let Some(item) = req.requests.iter().find(|item| !ids.insert(item.id.as_str())) {
+        return Err((axum::http::StatusCode::BAD_REQUEST, format!("Duplicate analytics id: {}", item.id)));
+    }
+
+    let results = futures_util::future::join_all(req.requests.into_iter().map(|item| {
+        let auth = auth.clone();
+        let state = state.clone();
+        let signed = SignedAmounts(signed.0);
+        async move { (item.id, run_batch_item(auth, signed, state, item.analytic, item.params).await) }
+    }))
+    .await;
+
+    let results = results
+        .into_iter()
+        .map(|(id, result)| {
+            let result = match result {
+                Ok(data) => AnalyticsBatchResult { status: axum::http::StatusCode::OK.as_u16(), data: Some(data), error: None },
+                Err((status, message)) => AnalyticsBatchResult { status: status.as_u16(), data: None, error: Some(message) },
+            };
+            (id, result)
+        })
+        .collect();
+
+    Ok(axum::Json(results))
+}
+
+// helper function to run one analytic from a batch through its route's handler, with the params as its query
+async fn run_batch_item(
+    auth: AuthenticatedUser,
+    signed: SignedAmounts,
+    state: AppState,
+    analytic: AnalyticsKind,
+    params: Option<serde_json::Value>,
+) -> Result<serde_json::Value, (axum::http::StatusCode, String)> {
+
+    fn query<T: serde::de::DeserializeOwned>(params: Option<serde_json::Value>) -> Result<axum::extract::Query<T>, (axum::http::StatusCode, String)> {
+        let params = params.unwrap_or_else(|| serde_json::json!({}));
+        serde_json::from_value(params)
+            .map(axum::extract::Query)
+            .map_err(|e| (axum::http::StatusCode::BAD_REQUEST, format!("Invalid params: {}", e)))
+    }
+
+    fn to_value<T: serde::Serialize>(axum::Json(data): axum::Json<T>) -> Result<serde_json::Value, (axum::http::StatusCode, String)> {
+        serde_json::to_value(data).map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+    }
+
+    let state = axum::extract::State(state);
+    match analytic {
+        AnalyticsKind::IncomeBySource => to_value(get_income_by_source(auth, query(params)?, state).await?),
+        AnalyticsKind::Forecast => to_value(get_spending_forecast(auth, query(params)?, state).await?),
+        AnalyticsKind::Cashflow => to_value(get_cashflow(auth, query(params)?, state).await?),
+        AnalyticsKind::Uncategorized => to_value(get_uncategorized_spending(auth, query(params)?, signed, state).await?),
+        AnalyticsKind::Largest => to_value(get_largest_transactions(auth, query(params)?, signed, state).await?),
+        AnalyticsKind::ByMerchant => to_value(get_spending_by_merchant(auth, query(params)?, state).await?),
+        AnalyticsKind::Compare => to_value(get_spending_comparison(auth, query(params)?, state).await?),
+        AnalyticsKind::ByWeekday => to_value(get_spending_by_weekday(auth, query(params)?, state).await?),
+        AnalyticsKind::Anomalies => to_value(get_anomalies(auth, query(params)?, signed, state).await?),
+        AnalyticsKind::SavingsRate => to_value(get_savings_rate(auth, query(params)?, state).await?),
+    }
+}
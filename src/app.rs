@@ -1,55 +1,576 @@
-use crate::models::AppState;
+use crate::config::Config;
+use crate::models::{AppState, AuthenticatedUser, RETRY_AFTER_SECS};
 use crate::handlers::*;
+use crate::analytics::*;
+use crate::export::*;
+use crate::households::*;
 
-// router function to set up all the routes
+// router function to set up the full app: the API routes plus the frontend static files
+// (or, in API-only deployments, a small landing response at the root path)
 pub fn build_app(state: AppState) -> axum::Router {
-    
-    use tower_http::cors::{CorsLayer, Any};
+
+    if !state.config.serve_frontend {
+        return api_router(state).route("/", axum::routing::get(root_handler));
+    }
+
+    // serve the frontend static files from ./frontend/dist
+    let frontend = frontend_router("./frontend/dist", &state.config);
+
+    api_router(state).merge(frontend)
+}
+
+// router function to serve the built frontend from `dist_dir`, with unknown paths falling back to index.html for the SPA
+pub fn frontend_router(dist_dir: &str, config: &Config) -> axum::Router {
+
+    use axum::http::{header::CACHE_CONTROL, HeaderValue, Response};
+    use tower_http::services::fs::ServeFileSystemResponseBody;
     use tower_http::services::{ServeDir, ServeFile};
+    use tower_http::set_header::SetResponseHeader;
 
-    // add a cors layer to allow requests from any origin (for development purposes)
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let dist_dir = std::path::Path::new(dist_dir);
+
+    // vite fingerprints everything under assets/, so a given file never changes and can be cached for a long time
+    // (only successful responses though, so a missing asset isn't cached as missing)
+    let asset_cache_control = HeaderValue::from_str(&format!("public, max-age={}, immutable", config.static_asset_max_age_secs)).unwrap();
+    let assets = SetResponseHeader::overriding(
+        ServeDir::new(dist_dir.join("assets")),
+        CACHE_CONTROL,
+        move |response: &Response<ServeFileSystemResponseBody>| {
+            response.status().is_success().then(|| asset_cache_control.clone())
+        },
+    );
+
+    // index.html (and anything else unhashed) is revalidated every time so a new deploy shows up straight away
+    let pages = SetResponseHeader::overriding(
+        ServeDir::new(dist_dir).fallback(ServeFile::new(dist_dir.join("index.html"))),
+        CACHE_CONTROL,
+        HeaderValue::from_static("no-cache"),
+    );
+
+    axum::Router::new()
+        .nest_service("/assets", assets)
+        .fallback_service(pages)
+}
+
+// router function to set up just the API routes (nested under /api), without the static frontend
+// so tests don't depend on the frontend build output
+pub fn api_router(state: AppState) -> axum::Router {
+
+    // add a cors layer (any origin by default, for development purposes)
+    let cors = cors_layer(&state.config);
+
+    // cap how many API requests are in flight at once (None when max_concurrent_requests is 0, meaning unlimited)
+    let in_flight = (state.config.max_concurrent_requests > 0)
+        .then(|| std::sync::Arc::new(tokio::sync::Semaphore::new(state.config.max_concurrent_requests)));
 
 
     // now, we set up our router
 
+    // the analytics routes are read-only aggregates, so repeat requests can be answered from the cache
+    let analytics = axum::Router::new()
+        .route("/analytics/income-by-source", axum::routing::get(get_income_by_source))
+        .route("/analytics/forecast", axum::routing::get(get_spending_forecast))
+        .route("/analytics/cashflow", axum::routing::get(get_cashflow))
+        .route("/analytics/uncategorized", axum::routing::get(get_uncategorized_spending))
+        .route("/analytics/largest", axum::routing::get(get_largest_transactions))
+        .route("/analytics/by-merchant", axum::routing::get(get_spending_by_merchant))
+        .route("/analytics/compare", axum::routing::get(get_spending_comparison))
+        .route("/analytics/by-weekday", axum::routing::get(get_spending_by_weekday))
+        .route("/analytics/anomalies", axum::routing::get(get_anomalies))
+        .route("/analytics/savings-rate", axum::routing::get(get_savings_rate))
+        .route("/analytics/batch", axum::routing::post(run_analytics_batch))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), cache_analytics_response));
+
+    // routes that can be switched off (see Features); a disabled group is left out entirely, so it's a 404
+    let features = &state.config.features;
+
+    let dev_routes = feature_routes(features.dev_routes, axum::Router::new()
+        .route("/test", axum::routing::get(test_handler))
+        .route("/test_state", axum::routing::get(test_state_handler))
+        .route("/test_db", axum::routing::get(test_db_handler)));
+
+    let two_factor = feature_routes(features.two_factor, axum::Router::new()
+        .route("/users/2fa/enable", axum::routing::post(enable_two_factor))
+        .route("/users/2fa/disable", axum::routing::post(disable_two_factor)));
+
+    let semantic_search = feature_routes(features.semantic_search, axum::Router::new()
+        .route("/transactions/search/semantic", axum::routing::post(semantic_transaction_search)));
+
+    let households = feature_routes(features.households, axum::Router::new()
+        .route("/households", axum::routing::post(create_household))
+        .route("/households", axum::routing::get(get_households))
+        .route("/households/invites", axum::routing::get(get_household_invites))
+        .route("/households/:id/members", axum::routing::post(add_household_member))
+        .route("/households/:id/accept", axum::routing::post(accept_household_invite))
+        .route("/households/:id/decline", axum::routing::post(decline_household_invite))
+        .route("/households/:id/budgets", axum::routing::post(upsert_household_budget)));
+
+    let exports = feature_routes(features.exports, axum::Router::new()
+        .route("/transactions/export.csv", axum::routing::get(export_transactions_csv))
+        .route("/budgets/export.csv", axum::routing::get(export_budgets_csv))
+        .route("/reports/monthly/:month/export", axum::routing::get(export_monthly_report)));
+
     // set up the api routes separately
     let api = axum::Router::new()
         // testing routes
-        .route("/test", axum::routing::get(test_handler))
-        .route("/test_state", axum::routing::get(test_state_handler))
-        .route("/test_db", axum::routing::get(test_db_handler))
+        .merge(dev_routes)
 
         // user routes
         .route("/users/register", axum::routing::post(register_user))
         .route("/users/login", axum::routing::post(user_login))
+        .route("/users/me", axum::routing::patch(update_current_user))
+        .route("/users/me/password", axum::routing::post(change_password))
+        .route("/users/me/reset", axum::routing::post(reset_user_data))
+        .route("/users/session", axum::routing::get(get_session_info))
+        .route("/users/token/renew", axum::routing::post(renew_token))
+        .merge(two_factor)
+        .route("/me/bootstrap", axum::routing::get(get_bootstrap))
+        .route("/auth/validate", axum::routing::get(validate_token))
 
         // transaction routes
         .route("/transactions", axum::routing::post(add_transaction))
         .route("/transactions", axum::routing::get(get_transactions))
+        .route("/transactions/recategorize", axum::routing::post(recategorize_transactions))
+        .route("/transactions/delete", axum::routing::post(bulk_delete_transactions))
+        .route("/transactions/clone-month", axum::routing::post(clone_month))
+        .route("/transactions/apply-rules", axum::routing::post(apply_category_rules))
+        .route("/transactions/archived", axum::routing::get(get_archived_transactions))
+        .route("/transactions/range", axum::routing::get(get_transaction_range))
+        .route("/transactions/descriptions", axum::routing::get(get_description_suggestions))
+        .route("/transactions/:id", axum::routing::put(update_transaction))
+        .route("/transactions/:id", axum::routing::delete(delete_transaction))
+        .route("/transactions/:id/clear", axum::routing::post(clear_transaction))
+        .route("/transactions/:id/duplicate", axum::routing::post(duplicate_transaction))
 
         // budget routes
         .route("/budgets", axum::routing::post(upsert_budget))
         .route("/budgets", axum::routing::get(get_budgets))
         .route("/budgets/progress", axum::routing::get(get_budget_progress))
+        .route("/budgets/progress/history", axum::routing::get(get_budget_progress_history))
+        .route("/budgets/overruns", axum::routing::get(get_budget_overruns))
+        .route("/budgets/adherence", axum::routing::get(get_budget_adherence))
+        .route("/budgets/copy", axum::routing::post(copy_budgets))
+        .route("/budgets/from-averages", axum::routing::post(budgets_from_averages))
+        .route("/budgets/suggest", axum::routing::get(suggest_budget))
+        .route("/budgets/limits", axum::routing::post(upsert_monthly_limits))
+        .route("/budgets/limits", axum::routing::get(get_monthly_limit_status))
+        .merge(households)
+
+        // export routes (csv and the monthly report)
+        .merge(exports)
+
+        // closed month routes
+        .route("/months/:month/close", axum::routing::post(close_month))
+        .route("/months/:month/reopen", axum::routing::post(reopen_month))
+
+        // category routes
+        .route("/categories/recent", axum::routing::get(get_recent_categories))
+        .route("/categories/overview", axum::routing::get(get_category_overview))
+        .route("/categories/rules", axum::routing::post(create_category_rule))
+        .route("/categories/rules", axum::routing::get(get_category_rules))
+        .route("/categories/rules/:id", axum::routing::delete(delete_category_rule))
+
+        // analytics routes (set up above, behind the response cache)
+        .merge(analytics)
 
         // semantic search routes
-        .route("/transactions/search/semantic", axum::routing::post(semantic_transaction_search))
+        .merge(semantic_search)
+
+
+        // any change a user makes clears their cached analytics
+        .layer(axum::middleware::from_fn_with_state(state.clone(), invalidate_analytics_cache))
+
+        // shed requests over the concurrency limit (inside CORS, so browsers can still read the 503)
+        .layer(axum::middleware::from_fn_with_state(in_flight, shed_excess_requests))
 
+        // turn away changes in read-only (demo) mode, before they take up a slot
+        .layer(axum::middleware::from_fn_with_state(state.config.read_only, reject_writes_when_read_only))
+
+        // rename JSON response keys to camelCase, when the instance asks for it
+        .layer(axum::middleware::from_fn_with_state(state.config.camel_case_json, camel_case_json))
+
+        // indent JSON responses on request, when the instance allows it
+        .layer(axum::middleware::from_fn_with_state(state.config.allow_pretty_json, pretty_print_json))
+
+        // tell clients when to retry a 503, whether it was shed above or the database connection was lost
+        .layer(axum::middleware::map_response(add_retry_after))
 
         // layer with CORS for development
         .layer(cors)
         .with_state(state);
 
-    // we nest the api under /api 
+    // we nest the api under /api
     axum::Router::new()
         .nest("/api", api)
-        // serve the frontend static files from ./frontend/dist
-        .fallback_service(
-            ServeDir::new("./frontend/dist")
-                .fallback(ServeFile::new("./frontend/dist/index.html")),
-        )
+}
+
+// helper function to keep a feature's routes only while the feature is on (otherwise an empty router, so they 404)
+fn feature_routes(enabled: bool, routes: axum::Router<AppState>) -> axum::Router<AppState> {
+    if enabled { routes } else { axum::Router::new() }
+}
+
+// middleware that adds a Retry-After header to 503 responses, since they're all transient
+async fn add_retry_after(mut response: axum::response::Response) -> axum::response::Response {
+    if response.status() == axum::http::StatusCode::SERVICE_UNAVAILABLE
+        && !response.headers().contains_key(axum::http::header::RETRY_AFTER)
+    {
+        response
+            .headers_mut()
+            .insert(axum::http::header::RETRY_AFTER, axum::http::HeaderValue::from(RETRY_AFTER_SECS));
+    }
+
+    response
+}
+
+// middleware that answers a repeat analytics request from the cache while it's fresh, and caches successful responses
+// (unauthenticated requests go straight through, for the handler to reject)
+async fn cache_analytics_response(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    auth: Option<AuthenticatedUser>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let cache = &state.analytics_cache;
+    let Some(auth) = auth.filter(|_| cache.is_enabled() && request.method() == axum::http::Method::GET) else {
+        return next.run(request).await;
+    };
+
+    let uri = request.uri().to_string();
+    if let Some(cached) = cache.get(auth.user_id, &uri) {
+        let mut response = axum::response::Response::new(axum::body::Body::from(cached.body));
+        if let Some(content_type) = cached.content_type {
+            response.headers_mut().insert(axum::http::header::CONTENT_TYPE, content_type);
+        }
+        return response;
+    }
+
+    let response = next.run(request).await;
+    if response.status() != axum::http::StatusCode::OK {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response body").into_response(),
+    };
+
+    cache.insert(auth.user_id, &uri, parts.headers.get(axum::http::header::CONTENT_TYPE).cloned(), bytes.clone());
+    axum::response::Response::from_parts(parts, axum::body::Body::from(bytes))
+}
+
+// middleware that clears a user's cached analytics whenever they send anything but a read (the analytics routes,
+// batch included, only ever read), whether or not it succeeds, since a failed request may still have written something first
+async fn invalidate_analytics_cache(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    auth: Option<AuthenticatedUser>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let is_read = matches!(*request.method(), axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS)
+        || request.uri().path().starts_with("/analytics/");
+
+    let response = next.run(request).await;
+
+    if let Some(auth) = auth.filter(|_| state.analytics_cache.is_enabled() && !is_read) {
+        state.analytics_cache.invalidate(auth.user_id);
+    }
+
+    response
+}
+
+// POST routes that only read (or just sign in), so they stay open in read-only mode
+const READ_ONLY_ALLOWED_POSTS: &[&str] = &[
+    "/users/login",
+    "/users/token/renew",
+    "/analytics/batch",
+    "/transactions/search/semantic",
+];
+
+// middleware that answers 403 to any request that could change data when READ_ONLY is on
+async fn reject_writes_when_read_only(
+    axum::extract::State(read_only): axum::extract::State<bool>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let is_read = matches!(*request.method(), axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS)
+        || (request.method() == axum::http::Method::POST && READ_ONLY_ALLOWED_POSTS.contains(&request.uri().path()));
+
+    if read_only && !is_read {
+        return (axum::http::StatusCode::FORBIDDEN, "This instance is read-only, so changes can't be made").into_response();
+    }
+
+    next.run(request).await
+}
+
+// query params that ask for indented JSON (any other params are left for the handler)
+#[derive(serde::Deserialize)]
+struct PrettyQuery {
+    pretty: Option<bool>,
+}
+
+// middleware that re-indents a JSON response when the request asks for it with `?pretty=true` or `X-Pretty: true`
+// (only when ALLOW_PRETTY_JSON is on; everything else, including non-JSON and streamed bodies, passes through untouched)
+async fn pretty_print_json(
+    axum::extract::State(allow_pretty_json): axum::extract::State<bool>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let wants_pretty = allow_pretty_json
+        && (axum::extract::Query::<PrettyQuery>::try_from_uri(request.uri()).is_ok_and(|query| query.pretty == Some(true))
+            || request.headers().get("x-pretty").is_some_and(|value| matches!(value.as_bytes(), b"true" | b"1")));
+
+    let response = next.run(request).await;
+    if !wants_pretty {
+        return response;
+    }
+
+    rewrite_json_body(response, indent_json).await
+}
+
+// middleware that renames every key in a JSON response from snake_case to camelCase, when CAMEL_CASE_JSON is on
+// (request bodies are still read in snake_case, and a batch response's top-level keys are the client's own ids, so
+// they're left as they were sent)
+// rewriting the text is safe because every other object in a response is a models.rs struct, so its keys are our own
+// field names; the batch map is the only place a client picks a key. values (categories, descriptions) are never
+// touched, only strings followed by ':'. it's done here rather than with serde's rename_all so the same structs can
+// serve both spellings, and the extra pass over the body only happens on instances that turn the flag on
+async fn camel_case_json(
+    axum::extract::State(camel_case_json): axum::extract::State<bool>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if !camel_case_json {
+        return next.run(request).await;
+    }
+
+    let keep_top_level_keys = request.uri().path() == "/analytics/batch";
+    let response = next.run(request).await;
+
+    rewrite_json_body(response, |json| camel_case_keys(json, keep_top_level_keys)).await
+}
+
+// helper function to rewrite the text of a JSON response body (non-JSON and invalid JSON bodies pass through untouched)
+async fn rewrite_json_body(
+    response: axum::response::Response,
+    rewrite: impl FnOnce(&str) -> String,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let is_json = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response body").into_response(),
+    };
+
+    // only rewrite what's actually valid JSON
+    let body = match std::str::from_utf8(&bytes) {
+        Ok(json) if serde_json::from_str::<serde::de::IgnoredAny>(json).is_ok() => rewrite(json),
+        _ => return axum::response::Response::from_parts(parts, axum::body::Body::from(bytes)),
+    };
+
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    axum::response::Response::from_parts(parts, axum::body::Body::from(body))
+}
+
+// helper function to camelCase the object keys of a JSON document, optionally leaving the outermost object's keys alone
+// (works on the text like indent_json, so key order and everything that isn't a key stay exactly as they were)
+fn camel_case_keys(json: &str, keep_top_level_keys: bool) -> String {
+    let mut out = String::with_capacity(json.len());
+    let mut depth = 0usize;
+    let mut chars = json.char_indices();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' => {
+                // find the closing quote, skipping escaped characters
+                let mut end = json.len();
+                let mut escaped = false;
+                for (i, c) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == '"' {
+                        end = i;
+                        break;
+                    }
+                }
+
+                let text = &json[start + 1..end];
+                let is_key = json[end + 1..].trim_start().starts_with(':');
+                out.push('"');
+                if is_key && !(keep_top_level_keys && depth == 1) {
+                    out.push_str(&to_camel_case(text));
+                } else {
+                    out.push_str(text);
+                }
+                out.push('"');
+            }
+            '{' | '[' => {
+                depth += 1;
+                out.push(c);
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+// helper function to turn a snake_case key into camelCase (anything that isn't a plain identifier is left alone)
+fn to_camel_case(key: &str) -> String {
+    if !key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        return key.to_string();
+    }
+
+    let mut out = String::with_capacity(key.len());
+    let mut upper_next = false;
+    for c in key.chars() {
+        if c == '_' && !out.is_empty() {
+            upper_next = true;
+        } else if upper_next {
+            out.push(c.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+// helper function to indent a JSON document by two spaces per level, in the same layout as serde_json's pretty printer
+// (works on the text rather than a parsed Value, so object keys keep their order)
+fn indent_json(json: &str) -> String {
+    fn newline(out: &mut String, depth: usize) {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    }
+
+    let mut out = String::with_capacity(json.len() * 2);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = json.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                out.push(c);
+                // empty objects and arrays stay on one line
+                if let Some(close) = chars.next_if(|next| matches!(next, '}' | ']')) {
+                    out.push(close);
+                } else {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+// middleware that answers 503 straight away when every in-flight slot is taken, rather than letting requests
+// queue up without bound behind the database pool
+async fn shed_excess_requests(
+    axum::extract::State(in_flight): axum::extract::State<Option<std::sync::Arc<tokio::sync::Semaphore>>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    // the permit is held until the response has been produced
+    let _permit = match in_flight {
+        Some(in_flight) => match in_flight.try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                return (axum::http::StatusCode::SERVICE_UNAVAILABLE, "Server is busy, try again shortly").into_response();
+            }
+        },
+        None => None,
+    };
+
+    next.run(request).await
+}
+
+// helper function to build the CORS layer from the config
+// browsers refuse credentialed responses with a wildcard origin, so credentialed mode echoes back only allowlisted origins
+fn cors_layer(config: &Config) -> tower_http::cors::CorsLayer {
+
+    use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+
+    let origins: Vec<axum::http::HeaderValue> = config
+        .cors_allowed_origins
+        .iter()
+        .filter_map(|origin| origin.parse().ok())
+        .collect();
+
+    if config.cors_allow_credentials {
+        if origins.is_empty() {
+            panic!("CORS_ALLOW_CREDENTIALS needs an explicit CORS_ALLOWED_ORIGINS list (a wildcard origin can't be used with credentials)");
+        }
+
+        return CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(AllowMethods::mirror_request())
+            .allow_headers(AllowHeaders::mirror_request())
+            .allow_credentials(true);
+    }
+
+    let allow_origin = if origins.is_empty() {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(origins)
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(Any)
+        .allow_headers(Any)
 }
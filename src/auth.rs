@@ -1,10 +1,10 @@
 use jsonwebtoken::{Algorithm, DecodingKey, Validation};
-use crate::models::{AppState, AuthenticatedUser, Claims};
+use crate::models::{AppState, AuthenticatedUser, Claims, VerifiedToken};
 
 /* helper functions */
 
-// helper function to verify a JWT and returns the user ID
-pub fn verify_jwt(token: &str, secret: &str) -> Result<(uuid::Uuid, usize), String> {
+// helper function to verify a JWT and return the user ID along with when it was issued and expires
+pub fn verify_jwt(token: &str, secret: &str) -> Result<VerifiedToken, String> {
     let decoding_key = DecodingKey::from_secret(secret.as_bytes());
     let mut validation = Validation::new(Algorithm::HS256);
     validation.validate_exp = true;
@@ -16,9 +16,29 @@ pub fn verify_jwt(token: &str, secret: &str) -> Result<(uuid::Uuid, usize), Stri
     // parse the user ID from the subject claim
     let user_id = uuid::Uuid::parse_str(&token_data.claims.sub)
         .map_err(|e| e.to_string())?;
-    let exp = token_data.claims.exp;
 
-    Ok((user_id, exp))
+    Ok(VerifiedToken {
+        user_id,
+        issued_at: token_data.claims.iat,
+        expires_at: token_data.claims.exp,
+    })
+}
+
+// helper function to pick the secret a JWT should be verified with, based on the kid in its header
+// tokens without a kid (issued before key ids were configured) are checked against the current secret
+pub fn select_jwt_secret<'a>(token: &str, state: &'a AppState) -> Result<&'a str, String> {
+    let header = jsonwebtoken::decode_header(token).map_err(|e| e.to_string())?;
+
+    match header.kid {
+        None => Ok(state.jwt_secret.as_str()),
+        Some(kid) if state.config.jwt_key_id.as_deref() == Some(kid.as_str()) => Ok(state.jwt_secret.as_str()),
+        Some(kid) => state
+            .config
+            .jwt_previous_secrets
+            .get(&kid)
+            .map(|secret| secret.as_str())
+            .ok_or_else(|| format!("Unknown key id: {}", kid)),
+    }
 }
 
 /* extractor functions */
@@ -48,8 +68,9 @@ impl axum::extract::FromRequestParts<AppState> for AuthenticatedUser {
                 "Invalid Authorization format, expected: Bearer <token>".to_string(),
             ))?;
 
-        // verify the JWT and extract the user ID
-        let (user_id, _exp) = verify_jwt(token, &state.jwt_secret)
+        // verify the JWT (with the key its kid points at) and extract the user ID
+        let verified = select_jwt_secret(token, state)
+            .and_then(|secret| verify_jwt(token, secret))
             .map_err(|_e| {
                 (
                     axum::http::StatusCode::UNAUTHORIZED,
@@ -57,7 +78,11 @@ impl axum::extract::FromRequestParts<AppState> for AuthenticatedUser {
                 )
             })?;
 
-        Ok(AuthenticatedUser { user_id })
+        Ok(AuthenticatedUser {
+            user_id: verified.user_id,
+            issued_at: verified.issued_at,
+            expires_at: verified.expires_at,
+        })
     }
 
 }
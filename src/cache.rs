@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/* response caching */
+
+// simple in-memory cache of analytics responses keyed by user and request URI, so dashboards that poll don't
+// re-run the same aggregate queries (a zero ttl disables it)
+#[derive(Clone)]
+pub struct AnalyticsCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<(uuid::Uuid, String), CachedResponse>>>,
+    misses: Arc<AtomicU64>, // lookups that had to go to the database
+}
+
+// a cached response body, along with its content type
+#[derive(Clone)]
+pub(crate) struct CachedResponse {
+    stored_at: Instant,
+    pub content_type: Option<axum::http::HeaderValue>,
+    pub body: axum::body::Bytes,
+}
+
+impl AnalyticsCache {
+    pub fn new(ttl: Duration) -> Self {
+        AnalyticsCache {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            misses: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    // cache for the analytics endpoints, using the configured ttl
+    pub fn for_analytics(config: &Config) -> Self {
+        AnalyticsCache::new(Duration::from_secs(config.analytics_cache_ttl_secs))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    // how many lookups have missed (and so been answered from the database) since startup
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    // looks up a fresh response for the user and URI, counting a miss if there isn't one
+    pub(crate) fn get(&self, user_id: uuid::Uuid, uri: &str) -> Option<CachedResponse> {
+        let entries = self.entries.lock().unwrap();
+        let cached = entries
+            .get(&(user_id, uri.to_string()))
+            .filter(|cached| cached.stored_at.elapsed() < self.ttl)
+            .cloned();
+
+        if cached.is_none() {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        cached
+    }
+
+    // stores a response, dropping anything that has expired along the way
+    pub(crate) fn insert(
+        &self,
+        user_id: uuid::Uuid,
+        uri: &str,
+        content_type: Option<axum::http::HeaderValue>,
+        body: axum::body::Bytes,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, cached| cached.stored_at.elapsed() < self.ttl);
+        entries.insert((user_id, uri.to_string()), CachedResponse { stored_at: Instant::now(), content_type, body });
+    }
+
+    // forgets everything cached for a user, after they've changed their data
+    pub(crate) fn invalidate(&self, user_id: uuid::Uuid) {
+        self.entries.lock().unwrap().retain(|(cached_user_id, _), _| *cached_user_id != user_id);
+    }
+}
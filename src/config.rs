@@ -0,0 +1,293 @@
+/* runtime configuration */
+
+// hard cap on access token lifetime in hours (30 days), whatever JWT_EXPIRATION_HOURS says
+pub const MAX_JWT_EXPIRATION_HOURS: u64 = 720;
+
+// longest startup waits between database retries in seconds (5 minutes), however far DB_CONNECT_BACKOFF has doubled
+pub const MAX_DB_CONNECT_BACKOFF_SECS: u64 = 300;
+
+// struct to hold optional runtime configuration, read from environment variables at startup
+#[derive(Clone, Debug)]
+pub struct Config {
+    // archive transactions dated more than this many years ago (None disables the archival job)
+    pub archive_after_years: Option<u32>,
+    // how often the archival job runs, in hours
+    pub archive_interval_hours: u64,
+    // whether pending transactions count towards budget progress and analytics (cleared only by default)
+    pub count_pending_transactions: bool,
+    // whether transactions may be dated after today (planned bills); they only count as spent once their date arrives
+    pub allow_future_transactions: bool,
+    // registrations allowed per client IP within the window below (0 disables the limit)
+    pub registration_rate_limit: usize,
+    // length of the registration rate-limit window, in seconds
+    pub registration_rate_window_secs: u64,
+    // whether to take the client IP from X-Forwarded-For (only enable behind a trusted proxy)
+    pub trust_forwarded_for: bool,
+    // key id (kid) stamped on newly issued JWTs, identifying JWT_SECRET
+    pub jwt_key_id: Option<String>,
+    // older signing secrets by key id, still accepted for tokens carrying that kid (for key rotation)
+    pub jwt_previous_secrets: std::collections::HashMap<String, String>,
+    // how many previous passwords are remembered and can't be reused (0 disables the check)
+    pub password_history_length: usize,
+    // origins allowed to call the API cross-origin (empty allows any origin)
+    pub cors_allowed_origins: Vec<String>,
+    // whether browsers may send cookies/credentials cross-origin (needs an explicit cors_allowed_origins)
+    pub cors_allow_credentials: bool,
+    // size of the database connection pool
+    pub db_max_connections: u32,
+    // most API requests handled at once, with any more answered 503 (0 disables the limit)
+    pub max_concurrent_requests: usize,
+    // how many times startup retries the first database connection/migrations before giving up
+    pub db_connect_retries: u32,
+    // initial delay between those retries in seconds, doubling after each attempt (up to MAX_DB_CONNECT_BACKOFF_SECS)
+    pub db_connect_backoff_secs: u64,
+    // how long browsers may cache the frontend's fingerprinted assets, in seconds
+    pub static_asset_max_age_secs: u64,
+    // most ids a single bulk request may carry
+    pub max_bulk_items: usize,
+    // most categories a user may budget in one month, keeping budget progress bounded (0 disables the limit)
+    pub max_budget_categories_per_month: usize,
+    // whether the API rejects every change with a 403 (for public demos over seeded data; logging in still works)
+    pub read_only: bool,
+    // how long analytics responses are cached per user, in seconds (0 disables the cache)
+    pub analytics_cache_ttl_secs: u64,
+    // whether `?pretty=true` (or an `X-Pretty: true` header) may ask for indented JSON, for poking at the API by hand
+    pub allow_pretty_json: bool,
+    // whether JSON responses use camelCase keys instead of snake_case (request bodies stay snake_case)
+    pub camel_case_json: bool,
+    // whether adding an expense checks spending limits (409 past a hard cap, a warning header past a soft one)
+    pub enforce_spending_limits: bool,
+    // whether budgets (and monthly limits) for months before the user's current one can still be written
+    pub allow_past_budget_edits: bool,
+    // how long newly issued access tokens last, in hours (capped at MAX_JWT_EXPIRATION_HOURS)
+    pub jwt_expiration_hours: u64,
+    // how close to expiry (in seconds) a token has to be before it can be renewed
+    pub token_renewal_window_secs: u64,
+    // whether to serve the frontend from ./frontend/dist (off for API-only deployments)
+    pub serve_frontend: bool,
+    // first day of the week, used to order weekday analytics
+    pub week_start: chrono::Weekday,
+    // whether transaction descriptions and merchants are encrypted at rest (needs notes_encryption_secret)
+    pub encrypt_notes: bool,
+    // server secret the per-user note keys are derived from; kept even with encrypt_notes off so old notes still open
+    pub notes_encryption_secret: Option<String>,
+    // prefix on the human-friendly transaction references built from each user's sequence number (e.g. "TXN-00042")
+    pub transaction_reference_prefix: String,
+    // which optional route groups are switched on
+    pub features: Features,
+}
+
+// struct to hold the per-feature switches; a disabled feature's routes aren't registered at all, so they answer 404
+// (everything is on by default, matching the API before the switches existed)
+#[derive(Clone, Debug)]
+pub struct Features {
+    // the /test, /test_state and /test_db routes, handy locally but not needed in production
+    pub dev_routes: bool,
+    // enabling and disabling two-factor sign-in (accounts that already have it still need their code to log in)
+    pub two_factor: bool,
+    // semantic transaction search, which calls out to OpenAI for every query
+    pub semantic_search: bool,
+    // creating and managing households and their shared budgets
+    pub households: bool,
+    // the CSV transaction and budget exports and the monthly report export
+    pub exports: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            dev_routes: true,
+            two_factor: true,
+            semantic_search: true,
+            households: true,
+            exports: true,
+        }
+    }
+}
+
+impl Features {
+    // read the switches from FEATURE_* environment variables, falling back to the defaults for anything unset
+    pub fn from_env() -> Self {
+        let defaults = Features::default();
+
+        Features {
+            dev_routes: env_flag_or("FEATURE_DEV_ROUTES", defaults.dev_routes),
+            two_factor: env_flag_or("FEATURE_TWO_FACTOR", defaults.two_factor),
+            semantic_search: env_flag_or("FEATURE_SEMANTIC_SEARCH", defaults.semantic_search),
+            households: env_flag_or("FEATURE_HOUSEHOLDS", defaults.households),
+            exports: env_flag_or("FEATURE_EXPORTS", defaults.exports),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            archive_after_years: None,
+            archive_interval_hours: 24,
+            count_pending_transactions: false,
+            allow_future_transactions: false,
+            registration_rate_limit: 3,
+            registration_rate_window_secs: 3600,
+            trust_forwarded_for: false,
+            jwt_key_id: None,
+            jwt_previous_secrets: std::collections::HashMap::new(),
+            password_history_length: 5,
+            cors_allowed_origins: Vec::new(),
+            cors_allow_credentials: false,
+            db_max_connections: 5,
+            max_concurrent_requests: 0,
+            db_connect_retries: 5,
+            db_connect_backoff_secs: 1,
+            static_asset_max_age_secs: 31_536_000, // one year
+            max_bulk_items: 1000,
+            max_budget_categories_per_month: 0,
+            read_only: false,
+            analytics_cache_ttl_secs: 0,
+            allow_pretty_json: false,
+            camel_case_json: false,
+            enforce_spending_limits: false,
+            allow_past_budget_edits: true,
+            jwt_expiration_hours: 24,
+            token_renewal_window_secs: 12 * 3600, // the second half of a token's 24 hour life
+            serve_frontend: true,
+            week_start: chrono::Weekday::Mon,
+            encrypt_notes: false,
+            notes_encryption_secret: None,
+            transaction_reference_prefix: "TXN-".to_string(),
+            features: Features::default(),
+        }
+    }
+}
+
+impl Config {
+    // build the config from environment variables, falling back to the defaults for anything unset
+    pub fn from_env() -> Self {
+        let defaults = Config::default();
+
+        Config {
+            archive_after_years: env_parse("ARCHIVE_AFTER_YEARS"),
+            archive_interval_hours: env_parse("ARCHIVE_INTERVAL_HOURS").unwrap_or(defaults.archive_interval_hours),
+            count_pending_transactions: env_flag_or("COUNT_PENDING_TRANSACTIONS", defaults.count_pending_transactions),
+            allow_future_transactions: env_flag_or("ALLOW_FUTURE_TRANSACTIONS", defaults.allow_future_transactions),
+            registration_rate_limit: env_parse("REGISTRATION_RATE_LIMIT").unwrap_or(defaults.registration_rate_limit),
+            registration_rate_window_secs: env_parse("REGISTRATION_RATE_WINDOW_SECS").unwrap_or(defaults.registration_rate_window_secs),
+            trust_forwarded_for: env_flag_or("TRUST_FORWARDED_FOR", defaults.trust_forwarded_for),
+            jwt_key_id: env_parse("JWT_KEY_ID"),
+            jwt_previous_secrets: std::env::var("JWT_PREVIOUS_SECRETS")
+                .map(|value| parse_key_list(&value))
+                .unwrap_or(defaults.jwt_previous_secrets),
+            password_history_length: env_parse("PASSWORD_HISTORY_LENGTH").unwrap_or(defaults.password_history_length),
+            cors_allowed_origins: std::env::var("CORS_ALLOWED_ORIGINS")
+                .map(|value| parse_list(&value))
+                .unwrap_or(defaults.cors_allowed_origins),
+            cors_allow_credentials: env_flag_or("CORS_ALLOW_CREDENTIALS", defaults.cors_allow_credentials),
+            db_max_connections: env_parse("DB_MAX_CONNECTIONS").unwrap_or(defaults.db_max_connections),
+            max_concurrent_requests: env_parse("MAX_CONCURRENT_REQUESTS").unwrap_or(defaults.max_concurrent_requests),
+            db_connect_retries: env_parse("DB_CONNECT_RETRIES").unwrap_or(defaults.db_connect_retries),
+            db_connect_backoff_secs: env_parse("DB_CONNECT_BACKOFF").unwrap_or(defaults.db_connect_backoff_secs),
+            static_asset_max_age_secs: env_parse("STATIC_ASSET_MAX_AGE_SECS").unwrap_or(defaults.static_asset_max_age_secs),
+            max_bulk_items: env_parse("MAX_BULK_ITEMS").unwrap_or(defaults.max_bulk_items),
+            max_budget_categories_per_month: env_parse("MAX_BUDGET_CATEGORIES_PER_MONTH").unwrap_or(defaults.max_budget_categories_per_month),
+            read_only: env_flag_or("READ_ONLY", defaults.read_only),
+            analytics_cache_ttl_secs: env_parse("ANALYTICS_CACHE_TTL_SECS").unwrap_or(defaults.analytics_cache_ttl_secs),
+            allow_pretty_json: env_flag_or("ALLOW_PRETTY_JSON", defaults.allow_pretty_json),
+            camel_case_json: env_flag_or("CAMEL_CASE_JSON", defaults.camel_case_json),
+            enforce_spending_limits: env_flag_or("ENFORCE_SPENDING_LIMITS", defaults.enforce_spending_limits),
+            allow_past_budget_edits: env_flag_or("ALLOW_PAST_BUDGET_EDITS", defaults.allow_past_budget_edits),
+            jwt_expiration_hours: env_parse("JWT_EXPIRATION_HOURS").unwrap_or(defaults.jwt_expiration_hours),
+            token_renewal_window_secs: env_parse("TOKEN_RENEWAL_WINDOW_SECS").unwrap_or(defaults.token_renewal_window_secs),
+            serve_frontend: env_flag_or("SERVE_FRONTEND", defaults.serve_frontend),
+            week_start: env_parse("WEEK_START").unwrap_or(defaults.week_start),
+            encrypt_notes: env_flag_or("ENCRYPT_NOTES", defaults.encrypt_notes),
+            notes_encryption_secret: env_parse("NOTES_ENCRYPTION_SECRET"),
+            transaction_reference_prefix: std::env::var("TRANSACTION_REFERENCE_PREFIX").unwrap_or(defaults.transaction_reference_prefix),
+            features: Features::from_env(),
+        }
+    }
+}
+
+impl Config {
+    // the access token lifetime actually used, with jwt_expiration_hours clamped to the hard maximum
+    pub fn effective_jwt_expiration_hours(&self) -> u64 {
+        self.jwt_expiration_hours.min(MAX_JWT_EXPIRATION_HOURS)
+    }
+}
+
+/* helper functions */
+
+// helper function to read and parse an environment variable, treating unset or unparsable values as None
+fn env_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|value| value.trim().parse().ok())
+}
+
+// helper function to read an on/off environment variable: only "true" or "1" (any case, surrounding spaces ignored)
+// turn it on, so `KEY=false` or an empty `KEY=` are off rather than counting as set
+pub fn env_flag(key: &str) -> bool {
+    parse_flag(std::env::var(key).ok().as_deref())
+}
+
+// helper function to read an on/off environment variable that has a default: unset keeps the default, and anything
+// that is set is read the same way as env_flag (so `KEY=1` and `KEY=TRUE` turn it on, anything else off)
+fn env_flag_or(key: &str, default: bool) -> bool {
+    match std::env::var(key) {
+        Ok(value) => parse_flag(Some(&value)),
+        Err(_) => default,
+    }
+}
+
+// helper function to parse an on/off value (None is an unset variable)
+pub fn parse_flag(value: Option<&str>) -> bool {
+    value.is_some_and(|value| {
+        let value = value.trim();
+        value.eq_ignore_ascii_case("true") || value == "1"
+    })
+}
+
+// helper function to parse a comma separated list of "kid:secret" pairs, skipping malformed entries
+fn parse_key_list(value: &str) -> std::collections::HashMap<String, String> {
+    value
+        .split(',')
+        .filter_map(|pair| pair.split_once(':'))
+        .map(|(kid, secret)| (kid.trim().to_string(), secret.trim().to_string()))
+        .filter(|(kid, secret)| !kid.is_empty() && !secret.is_empty())
+        .collect()
+}
+
+// helper function to parse a comma separated list, skipping empty entries
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test that only "true" and "1" count as on
+    #[test]
+    fn test_parse_flag() {
+        for truthy in ["true", "1", "TRUE", " true "] {
+            assert!(parse_flag(Some(truthy)), "{:?} should be on", truthy);
+        }
+        for falsy in ["", "false", "0", "no", "FALSE", "yes"] {
+            assert!(!parse_flag(Some(falsy)), "{:?} should be off", falsy);
+        }
+        assert!(!parse_flag(None));
+    }
+
+    // test that READ_ONLY=1 turns read-only mode on, not just READ_ONLY=true
+    #[test]
+    fn test_read_only_accepts_1() {
+        // no other test reads or sets READ_ONLY, so changing it here can't race with them
+        unsafe { std::env::set_var("READ_ONLY", "1") };
+        let config = Config::from_env();
+        unsafe { std::env::remove_var("READ_ONLY") };
+
+        assert!(config.read_only);
+    }
+}
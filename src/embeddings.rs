@@ -87,6 +87,9 @@ pub async fn store_transaction_embedding(
     embedding_text: &str,
     embedding: Vec<f32>,
 ) -> Result<(), (StatusCode, String)> {
+    // the embedding text repeats the description and merchant, so it's encrypted along with them
+    let embedding_text = crate::notes::seal_note(&state.config, user_id, Some(embedding_text));
+
     // we use the pgvector extension to store the embedding vector in the database
     sqlx::query(
         "INSERT INTO transaction_embeddings (transaction_id, user_id, embedding_text, embedding) VALUES ($1, $2, $3, $4)"
@@ -97,7 +100,7 @@ pub async fn store_transaction_embedding(
     .bind(Vector::from(embedding)) // insert as a pgvector type
     .execute(&state.pool)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(crate::handlers::db_error)?;
 
     Ok(())
 }
@@ -115,10 +118,15 @@ mod tests {
     fn test_transaction_string_embedding() {
         let req = AddTransactionRequest {
             amount: Decimal::new(1234, 2), // $12.34
-            date: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            date: Some(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()),
             category: Some("Food".to_string()),
             description: Some("Lunch at cafe".to_string()),
             kind: TransactionKind::Expense,
+            source: None,
+            status: None,
+            exclude_from_budget: None,
+            merchant: None,
+            location: None,
         };
 
         let embedding_string = req.transaction_string_embedding();
@@ -134,10 +142,15 @@ mod tests {
     fn test_transaction_string_embedding_missing_fields() {
         let req = AddTransactionRequest {
             amount: Decimal::new(5000, 2), // $50.00
-            date: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            date: Some(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()),
             category: None,
             description: None,
             kind: TransactionKind::Income,
+            source: None,
+            status: None,
+            exclude_from_budget: None,
+            merchant: None,
+            location: None,
         };
 
         let embedding_string = req.transaction_string_embedding();
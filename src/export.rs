@@ -0,0 +1,255 @@
+use futures_util::StreamExt;
+use sqlx::Row;
+
+use crate::handlers::{db_error, get_budget_progress, get_budgets, month_start_of, next_month_start_of, transaction_from_row};
+use crate::models::*;
+
+/* exports */
+
+// route for exporting the user's transactions as CSV
+// rows are streamed out as they're fetched from the database, so memory stays flat however much history there is
+pub(crate) async fn export_transactions_csv(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<TransactionExportQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, String)> {
+
+    // rows are produced by a background task and handed to the response body through a bounded channel,
+    // so a slow client applies backpressure to the database fetch instead of rows piling up in memory
+    let (sender, receiver) = tokio::sync::mpsc::channel::<Result<String, sqlx::Error>>(64);
+    let pool = state.pool.clone();
+    let config = state.config.clone();
+    let user_id = auth.user_id;
+
+    tokio::spawn(async move {
+        if sender.send(Ok(TRANSACTIONS_CSV_HEADER.to_string())).await.is_err() {
+            return;
+        }
+
+        let mut rows = sqlx::query(
+            "SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at
+            FROM transactions
+            WHERE user_id = $1
+            AND ($2::date IS NULL OR date >= $2)
+            AND ($3::date IS NULL OR date <= $3)
+            AND ($4::text IS NULL OR category = $4)
+            ORDER BY date ASC, id ASC"
+        )
+        .bind(user_id)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(query.category)
+        .fetch(&pool);
+
+        while let Some(row) = rows.next().await {
+            let line = row.map(|row| transaction_csv_line(&transaction_from_row(&row, user_id, &config)));
+            let failed = line.is_err();
+
+            // stop if the client has gone away, or after passing a database error along (which aborts the body)
+            if sender.send(line).await.is_err() || failed {
+                return;
+            }
+        }
+    });
+
+    let body_stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    });
+
+    csv_response("transactions.csv", axum::body::Body::from_stream(body_stream))
+}
+
+// route for exporting the user's budgets as CSV, optionally limited to a range of months
+// budgets are one row per category per month, so these are small enough to build in one go
+pub(crate) async fn export_budgets_csv(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<DateRangeQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, String)> {
+
+    // any day in a month selects that whole month
+    let rows = sqlx::query(
+        "SELECT month, category, amount
+        FROM budgets
+        WHERE user_id = $1
+        AND ($2::date IS NULL OR month >= $2)
+        AND ($3::date IS NULL OR month <= $3)
+        ORDER BY month ASC, category ASC"
+    )
+    .bind(auth.user_id)
+    .bind(query.from.map(month_start_of))
+    .bind(query.to.map(month_start_of))
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let mut body = BUDGETS_CSV_HEADER.to_string();
+    for row in rows {
+        let month: chrono::NaiveDate = row.get("month");
+        let category: String = row.get("category");
+        let amount: sqlx::types::Decimal = row.get("amount");
+
+        body.push_str(&csv_line(&[&month.to_string(), &category, &amount.to_string()]));
+    }
+
+    csv_response("budgets.csv", axum::body::Body::from(body))
+}
+
+// route for exporting one month's full report (its transactions, budgets and budget progress) as a single download,
+// either one JSON object or a CSV file with a section for each part
+pub(crate) async fn export_monthly_report(
+    auth: AuthenticatedUser,
+    axum::extract::Path(month): axum::extract::Path<chrono::NaiveDate>,
+    axum::extract::Query(query): axum::extract::Query<ReportExportQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let month = month_start_of(month);
+
+    let rows = sqlx::query(
+        "SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at
+        FROM transactions
+        WHERE user_id = $1 AND date >= $2 AND date < $3
+        ORDER BY date ASC, id ASC"
+    )
+    .bind(auth.user_id)
+    .bind(month)
+    .bind(next_month_start_of(month))
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    let transactions: Vec<Transaction> = rows
+        .iter()
+        .map(|row| transaction_from_row(row, auth.user_id, &state.config))
+        .collect();
+
+    let axum::Json(budgets) = get_budgets(
+        auth.clone(),
+        axum::extract::Query(BudgetQuery { month: Some(month) }),
+        axum::extract::State(state.clone()),
+    ).await?;
+
+    let axum::Json(budget_progress) = get_budget_progress(
+        auth,
+        axum::extract::Query(BudgetProgressQuery { month: Some(month), category: None, household_id: None }),
+        axum::extract::State(state),
+    ).await?;
+
+    let file_stem = format!("report-{}", month.format("%Y-%m"));
+
+    match query.format.unwrap_or(ReportFormat::Json) {
+        ReportFormat::Json => {
+            let disposition = format!("attachment; filename=\"{}.json\"", file_stem);
+            let report = MonthlyReport { month, transactions, budgets, budget_progress };
+            Ok(([(axum::http::header::CONTENT_DISPOSITION, disposition)], axum::Json(report)).into_response())
+        }
+        ReportFormat::Csv => {
+            // sections are separated by a blank line, each starting with a "# name" line and then its own header
+            let mut body = String::from("# transactions\n");
+            body.push_str(TRANSACTIONS_CSV_HEADER);
+            for transaction in &transactions {
+                body.push_str(&transaction_csv_line(transaction));
+            }
+
+            body.push_str("\n# budgets\n");
+            body.push_str(BUDGETS_CSV_HEADER);
+            for budget in &budgets {
+                body.push_str(&csv_line(&[&budget.month.to_string(), &budget.category, &budget.amount.to_string()]));
+            }
+
+            body.push_str("\n# budget progress\n");
+            body.push_str(BUDGET_PROGRESS_CSV_HEADER);
+            for progress in &budget_progress {
+                body.push_str(&csv_line(&[
+                    &progress.category,
+                    &progress.budget_amount.to_string(),
+                    &progress.spent.to_string(),
+                    &progress.remaining.to_string(),
+                ]));
+            }
+
+            csv_response(&format!("{}.csv", file_stem), axum::body::Body::from(body))
+        }
+    }
+}
+
+/* helper functions */
+
+const BUDGETS_CSV_HEADER: &str = "month,category,amount\n";
+
+const BUDGET_PROGRESS_CSV_HEADER: &str = "category,budget_amount,spent,remaining\n";
+
+const TRANSACTIONS_CSV_HEADER: &str = "id,date,kind,amount,category,description,source,status\n";
+
+// helper function to turn a transaction into one CSV line (including the trailing newline)
+fn transaction_csv_line(transaction: &Transaction) -> String {
+    let kind = match transaction.kind {
+        TransactionKind::Income => "income",
+        TransactionKind::Expense => "expense",
+    };
+    let status = match transaction.status {
+        TransactionStatus::Pending => "pending",
+        TransactionStatus::Cleared => "cleared",
+    };
+
+    csv_line(&[
+        &transaction.id.to_string(),
+        &transaction.date.to_string(),
+        kind,
+        &transaction.amount.to_string(),
+        transaction.category.as_deref().unwrap_or(""),
+        transaction.description.as_deref().unwrap_or(""),
+        transaction.source.as_deref().unwrap_or(""),
+        status,
+    ])
+}
+
+// helper function to join fields into a CSV line, quoting any field that needs it
+pub(crate) fn csv_line(fields: &[&str]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+// helper function to escape a single CSV field (RFC 4180: quote fields containing commas, quotes or newlines)
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// helper function to wrap a CSV body in a response that downloads as the given file name
+pub(crate) fn csv_response(
+    file_name: &str,
+    body: axum::body::Body,
+) -> Result<axum::response::Response, (axum::http::StatusCode, String)> {
+    axum::response::Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(
+            axum::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", file_name),
+        )
+        .body(body)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+// unit test
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // check that fields are only quoted when they need to be
+    #[test]
+    fn test_csv_line_escaping() {
+        let line = csv_line(&["plain", "with, comma", "with \"quotes\"", "two\nlines", ""]);
+
+        assert_eq!(line, "plain,\"with, comma\",\"with \"\"quotes\"\"\",\"two\nlines\",\n");
+    }
+}
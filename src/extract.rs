@@ -0,0 +1,86 @@
+use axum::extract::{FromRequest, FromRequestParts};
+use axum::extract::rejection::JsonRejection;
+use axum::http::StatusCode;
+
+use crate::models::{Transaction, TransactionKind};
+
+/* extractor functions */
+
+// drop-in replacement for axum's Json extractor that turns its rejections into clear (StatusCode, String) errors,
+// most importantly a 415 when the client forgets the Content-Type: application/json header
+pub(crate) struct JsonBody<T>(pub T);
+
+#[axum::async_trait]
+impl<S, T> FromRequest<S> for JsonBody<T>
+where
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request(req: axum::extract::Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(JsonBody(value)),
+            Err(rejection) => Err(json_rejection_error(rejection)),
+        }
+    }
+}
+
+// extractor for the `?signed=true` response convention: expense amounts come back negated and income as is, so
+// a client can sum a list of transactions straight away. it only ever changes the amount on individual transactions;
+// totals and other aggregates keep their usual meaning (cashflow's net is already signed, expense totals stay positive)
+pub(crate) struct SignedAmounts(pub bool);
+
+// query param for the signed convention (any other params are left for the handler)
+#[derive(serde::Deserialize)]
+struct SignedQuery {
+    signed: Option<bool>,
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for SignedAmounts
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut axum::http::request::Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        axum::extract::Query::<SignedQuery>::try_from_uri(&parts.uri)
+            .map(|axum::extract::Query(query)| SignedAmounts(query.signed.unwrap_or(false)))
+            .map_err(|rejection| (StatusCode::BAD_REQUEST, rejection.body_text()))
+    }
+}
+
+impl SignedAmounts {
+    // negates an expense's amount when the signed convention was asked for
+    pub(crate) fn apply(&self, transaction: &mut Transaction) {
+        if self.0 && matches!(transaction.kind, TransactionKind::Expense) {
+            transaction.amount = -transaction.amount;
+        }
+    }
+
+    pub(crate) fn apply_all(&self, transactions: &mut [Transaction]) {
+        transactions.iter_mut().for_each(|transaction| self.apply(transaction));
+    }
+}
+
+/* helper functions */
+
+// helper function to map a Json rejection to a status code and a message that says what to fix
+fn json_rejection_error(rejection: JsonRejection) -> (StatusCode, String) {
+    match rejection {
+        JsonRejection::MissingJsonContentType(_) => (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Expected request with Content-Type: application/json".to_string(),
+        ),
+        JsonRejection::JsonSyntaxError(e) => (
+            StatusCode::BAD_REQUEST,
+            format!("Malformed JSON body: {}", e.body_text()),
+        ),
+        JsonRejection::JsonDataError(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid JSON body: {}", e.body_text()),
+        ),
+        other => (other.status(), other.body_text()),
+    }
+}
@@ -6,30 +6,578 @@ use jsonwebtoken::{Algorithm, EncodingKey, Header};
 use std::time::{SystemTime, UNIX_EPOCH};
 use pgvector::Vector;
 use sqlx::Row;
+use sqlx::types::Decimal;
 
 use crate::models::*;
 use crate::embeddings::*;
+use crate::extract::{JsonBody, SignedAmounts};
+use crate::households::scope_user_ids;
+use crate::notes::{open_note, seal_note};
+use crate::rate_limit::client_ip;
+use crate::totp::{base32_encode, provisioning_uri, verify_totp};
+
+/* helper functions */
+
+// helper function to map a transactions row (id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at) into a Transaction
+// (decrypting the description and merchant if they were stored encrypted)
+pub(crate) fn transaction_from_row(row: &sqlx::postgres::PgRow, user_id: uuid::Uuid, config: &crate::config::Config) -> Transaction {
+    let kind_str: String = row.get("kind");
+    let status_str: String = row.get("status");
+    let seq: i64 = row.get("seq");
+
+    Transaction {
+        id: row.get("id"),
+        user_id,
+        amount: row.get("amount"),
+        kind: match kind_str.as_str() {
+            "income" => TransactionKind::Income,
+            "expense" => TransactionKind::Expense,
+            _ => panic!("Invalid transaction kind in database"),
+        },
+        category: row.get("category"),
+        date: row.get("date"),
+        description: open_note(config, user_id, row.get("description")),
+        source: row.get("source"),
+        status: match status_str.as_str() {
+            "pending" => TransactionStatus::Pending,
+            "cleared" => TransactionStatus::Cleared,
+            _ => panic!("Invalid transaction status in database"),
+        },
+        exclude_from_budget: row.get("exclude_from_budget"),
+        merchant: open_note(config, user_id, row.get("merchant")),
+        location: row.get("location"),
+        seq,
+        reference: format!("{}{:05}", config.transaction_reference_prefix, seq),
+        updated_at: row.get("updated_at"),
+    }
+}
 
-/* user information */
+// helper function to get the stored form of a transaction kind
+pub(crate) fn transaction_kind_str(kind: &TransactionKind) -> &'static str {
+    match kind {
+        TransactionKind::Income => "income",
+        TransactionKind::Expense => "expense",
+    }
+}
 
-// route for user registration
-pub(crate) async fn register_user(
-    axum::extract::State(state): axum::extract::State<AppState>,
-    axum::extract::Json(user_information): axum::extract::Json<RegisterUser>
-) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+// helper function to get today's date in the user's timezone (postgres knows the IANA zone names)
+// falls back to the UTC date if the user can't be found
+pub(crate) async fn user_today(pool: &sqlx::PgPool, user_id: uuid::Uuid) -> Result<chrono::NaiveDate, (axum::http::StatusCode, String)> {
+    let today: Option<chrono::NaiveDate> = sqlx::query_scalar("SELECT (now() AT TIME ZONE timezone)::date FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?;
 
+    Ok(today.unwrap_or_else(|| chrono::Utc::now().date_naive()))
+}
 
-    // we use argon2 for password hashing
+// helper function to reject budget writes for months before the user's current one, unless the instance allows them
+pub(crate) async fn ensure_budget_month_editable(state: &AppState, user_id: uuid::Uuid, month: chrono::NaiveDate) -> Result<(), (axum::http::StatusCode, String)> {
+    if !state.config.allow_past_budget_edits
+        && month_start_of(month) < month_start_of(user_today(&state.pool, user_id).await?)
+    {
+        return Err((axum::http::StatusCode::FORBIDDEN, "Budgets for past months can't be changed".to_string()));
+    }
+
+    Ok(())
+}
+
+// helper function to lock a user's month of budgets until the surrounding database transaction ends, so concurrent
+// writes can't both pass the category cap check (an advisory lock, since a new category has no row to lock yet)
+async fn lock_budget_month(
+    conn: &mut sqlx::PgConnection,
+    user_id: uuid::Uuid,
+    month: chrono::NaiveDate,
+) -> Result<(), (axum::http::StatusCode, String)> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('budgets:' || $1::text || ':' || $2::text))")
+        .bind(user_id)
+        .bind(month)
+        .execute(conn)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
+// helper function to count the categories a user has budgeted in a month
+async fn count_budget_categories(
+    conn: &mut sqlx::PgConnection,
+    user_id: uuid::Uuid,
+    month: chrono::NaiveDate,
+) -> Result<i64, (axum::http::StatusCode, String)> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM budgets WHERE user_id = $1 AND month = $2")
+        .bind(user_id)
+        .bind(month)
+        .fetch_one(conn)
+        .await
+        .map_err(db_error)
+}
+
+// helper function to reject a write that added budget categories to a month past max_budget_categories_per_month,
+// given the month's category count before and after it (changes that add nothing are fine, even over the cap).
+// the write and both counts should share a database transaction holding lock_budget_month
+fn ensure_within_budget_category_cap(config: &crate::config::Config, before: i64, after: i64) -> Result<(), (axum::http::StatusCode, String)> {
+    let cap = config.max_budget_categories_per_month;
+    if cap > 0 && after > before && after as usize > cap {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            format!("This month already has the maximum of {} budget categories", cap),
+        ));
+    }
+
+    Ok(())
+}
+
+// helper function to reject a transaction dated after the user's today, unless the instance allows planned transactions
+pub(crate) async fn ensure_not_future(state: &AppState, user_id: uuid::Uuid, date: chrono::NaiveDate) -> Result<(), (axum::http::StatusCode, String)> {
+    if !state.config.allow_future_transactions && date > user_today(&state.pool, user_id).await? {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "date can't be in the future".to_string()));
+    }
+
+    Ok(())
+}
+
+// helper function to map a database error to a response: connection-level failures (the database restarting or
+// failing over, or the pool unable to reach it) are a 503 the client can retry, anything else is a 500
+pub(crate) fn db_error(e: sqlx::Error) -> (axum::http::StatusCode, String) {
+    if is_connection_error(&e) {
+        tracing::error!("Database connection error: {}", e);
+        return (axum::http::StatusCode::SERVICE_UNAVAILABLE, "Database is unavailable, try again shortly".to_string());
+    }
+
+    (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+// helper function to tell connection-level database errors apart from errors in the query itself
+pub fn is_connection_error(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::WorkerCrashed => true,
+        // SQLSTATE class 08 is connection exceptions; 57P01-57P03 are the server shutting down or not accepting connections yet
+        sqlx::Error::Database(db_err) => db_err
+            .code()
+            .is_some_and(|code| code.starts_with("08") || matches!(code.as_ref(), "57P01" | "57P02" | "57P03")),
+        _ => false,
+    }
+}
+
+// helper function to find the category from the user's first matching category rule for a description, if any
+pub(crate) async fn rule_category_for(pool: &sqlx::PgPool, user_id: uuid::Uuid, description: &str) -> Result<Option<String>, (axum::http::StatusCode, String)> {
+    sqlx::query_scalar(
+        "SELECT category
+        FROM category_rules
+        WHERE user_id = $1 AND strpos(lower($2), lower(match_contains)) > 0
+        ORDER BY priority ASC, created_at ASC
+        LIMIT 1"
+    )
+    .bind(user_id)
+    .bind(description)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)
+}
+
+// helper function to check spending limits are whole-cent amounts, with the soft limit no higher than the hard cap
+pub(crate) fn ensure_limits_valid(limits: &BudgetLimits) -> Result<(), (axum::http::StatusCode, String)> {
+    for limit in [limits.warn_at, limits.cap_at].into_iter().flatten() {
+        ensure_amount_fits(limit, DEFAULT_AMOUNT_SCALE)?;
+    }
+
+    if let (Some(warn_at), Some(cap_at)) = (limits.warn_at, limits.cap_at)
+        && warn_at > cap_at
+    {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "warn_at must not be above cap_at".to_string()));
+    }
+
+    Ok(())
+}
+
+// helper function to work out which limits an amount of spending has crossed, as (warn crossed, cap exceeded)
+// (reaching the soft limit is enough to warn, while the cap itself is still allowed)
+pub(crate) fn limits_crossed(limits: &BudgetLimits, spent: Decimal) -> (bool, bool) {
+    (
+        limits.warn_at.is_some_and(|warn_at| spent >= warn_at),
+        limits.cap_at.is_some_and(|cap_at| spent > cap_at),
+    )
+}
+
+// helper function to check a new expense against its month's limits, for its category and overall
+// returns which soft limits it crosses ("category" and/or "overall"), or a 409 if it would go past a hard cap.
+// planned expenses later in the month count here, since that money is already spoken for. `replacing` is the
+// transaction being edited, if any, so its current amount isn't counted alongside the new one
+async fn check_spending_limits(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    replacing: Option<uuid::Uuid>,
+    category: Option<&str>,
+    date: chrono::NaiveDate,
+    amount: Decimal,
+) -> Result<Vec<&'static str>, (axum::http::StatusCode, String)> {
+    let month = month_start_of(date);
+
+    let row = sqlx::query(
+        "WITH spend AS (
+            SELECT amount, category
+            FROM transactions
+            WHERE user_id = $1
+            AND kind = 'expense'
+            AND date >= $2 AND date < $3
+            AND (status = 'cleared' OR $5)
+            AND NOT exclude_from_budget
+            AND id IS DISTINCT FROM $6
+        )
+        SELECT
+            b.warn_at AS category_warn_at,
+            b.cap_at AS category_cap_at,
+            m.warn_at AS overall_warn_at,
+            m.cap_at AS overall_cap_at,
+            (SELECT COALESCE(SUM(amount), 0) FROM spend WHERE category = $4)::numeric AS category_spent,
+            (SELECT COALESCE(SUM(amount), 0) FROM spend)::numeric AS overall_spent
+        FROM (SELECT 1) one
+        LEFT JOIN budgets b ON b.user_id = $1 AND b.month = $2 AND b.category = $4
+        LEFT JOIN monthly_limits m ON m.user_id = $1 AND m.month = $2"
+    )
+    .bind(user_id)
+    .bind(month)
+    .bind(next_month_start_of(month))
+    .bind(category)
+    .bind(state.config.count_pending_transactions)
+    .bind(replacing)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let checks = [
+        ("category", BudgetLimits { warn_at: row.get("category_warn_at"), cap_at: row.get("category_cap_at") }, row.get::<Decimal, _>("category_spent")),
+        ("overall", BudgetLimits { warn_at: row.get("overall_warn_at"), cap_at: row.get("overall_cap_at") }, row.get::<Decimal, _>("overall_spent")),
+    ];
+
+    let mut warnings = Vec::new();
+    for (scope, limits, spent) in checks {
+        let (warn_crossed, cap_exceeded) = limits_crossed(&limits, spent + amount);
+        if cap_exceeded {
+            return Err((
+                axum::http::StatusCode::CONFLICT,
+                format!("This expense would take {} spending for the month past its cap of {}", scope, limits.cap_at.unwrap_or_default()),
+            ));
+        }
+        if warn_crossed {
+            warnings.push(scope);
+        }
+    }
+
+    Ok(warnings)
+}
+
+// helper function to get the first day of the month containing `date`
+pub(crate) fn month_start_of(date: chrono::NaiveDate) -> chrono::NaiveDate {
+    use chrono::Datelike;
+
+    chrono::NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap()
+}
+
+// helper function to work out how far through a month `today` is, as (days elapsed, days in the month)
+// (0 days elapsed for future months and the whole month for past ones; today counts as an elapsed day)
+pub(crate) fn month_days_elapsed(month_start: chrono::NaiveDate, today: chrono::NaiveDate) -> (i64, i64) {
+    let next_month_start = next_month_start_of(month_start);
+    let days_in_month = (next_month_start - month_start).num_days();
+
+    let days_elapsed = if today < month_start {
+        0
+    } else if today >= next_month_start {
+        days_in_month
+    } else {
+        (today - month_start).num_days() + 1
+    };
+
+    (days_elapsed, days_in_month)
+}
+
+// helper function to get the first day of the following month (the exclusive end bound for month queries)
+pub(crate) fn next_month_start_of(date: chrono::NaiveDate) -> chrono::NaiveDate {
+    use chrono::Datelike;
+
+    let (ny, nm) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+
+    chrono::NaiveDate::from_ymd_opt(ny, nm, 1).unwrap()
+}
 
+// helper function to hash a password with argon2 and a random salt
+pub(crate) fn hash_password(password: &str) -> Result<String, (axum::http::StatusCode, String)> {
     // create a random salt
     let salt = SaltString::generate(&mut OsRng);
 
     // now hash the password
     let password_hash = Argon2::default()
-        .hash_password(user_information.password.as_bytes(), &salt)
+        .hash_password(password.as_bytes(), &salt)
         .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .to_string();
 
+    Ok(password_hash)
+}
+
+// helper function to check a password against a stored argon2 hash
+pub(crate) fn password_matches(password: &str, password_hash: &str) -> Result<bool, (axum::http::StatusCode, String)> {
+    let parsed_hash = argon2::PasswordHash::new(password_hash)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
+}
+
+// helper function to reject a text field longer than `max` characters
+pub(crate) fn ensure_max_length(field: &str, value: &str, max: usize) -> Result<(), (axum::http::StatusCode, String)> {
+    if value.chars().count() > max {
+        return Err((axum::http::StatusCode::BAD_REQUEST, format!("{} must be at most {} characters", field, max)));
+    }
+
+    Ok(())
+}
+
+// helper function to reject an amount with more than `scale` decimal places or more than MAX_AMOUNT_WHOLE_DIGITS
+// before the point, before postgres turns it into a 500 (or silently rounds away the extra decimal places)
+pub(crate) fn ensure_amount_fits(amount: Decimal, scale: u32) -> Result<(), (axum::http::StatusCode, String)> {
+    if amount.normalize().scale() > scale {
+        return Err((axum::http::StatusCode::BAD_REQUEST, format!("amount must have at most {} decimal places", scale)));
+    }
+
+    if amount.abs().trunc() >= Decimal::from(10i64.pow(MAX_AMOUNT_WHOLE_DIGITS)) {
+        return Err((axum::http::StatusCode::BAD_REQUEST, format!("amount must have at most {} digits before the decimal point", MAX_AMOUNT_WHOLE_DIGITS)));
+    }
+
+    Ok(())
+}
+
+// helper function to look up how many decimal places the user's transaction amounts may have
+pub(crate) async fn user_amount_scale(pool: &sqlx::PgPool, user_id: uuid::Uuid) -> Result<u32, (axum::http::StatusCode, String)> {
+    let scale: Option<i16> = sqlx::query_scalar("SELECT amount_scale FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(scale.map_or(DEFAULT_AMOUNT_SCALE, |scale| scale as u32))
+}
+
+// helper function to check a username is 3-50 characters of letters, digits, '_', '-' or '.'
+pub(crate) fn validate_username(username: &str) -> Result<(), (axum::http::StatusCode, String)> {
+    let length = username.chars().count();
+    if !(3..=MAX_USERNAME_LENGTH).contains(&length) {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            format!("Username must be between 3 and {} characters", MAX_USERNAME_LENGTH),
+        ));
+    }
+
+    if !username.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            "Username may only contain letters, digits, '_', '-' and '.'".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// helper function to check a two-factor code, falling back to spending one of the user's unused backup codes
+pub(crate) async fn second_factor_matches(
+    pool: &sqlx::PgPool,
+    user_id: uuid::Uuid,
+    totp_secret: &[u8],
+    code: &str,
+) -> Result<bool, (axum::http::StatusCode, String)> {
+    let code = code.trim();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    if verify_totp(totp_secret, code, now) {
+        return Ok(true);
+    }
+
+    let backup_codes = sqlx::query("SELECT id, code_hash FROM totp_backup_codes WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)?;
+
+    for row in backup_codes {
+        let code_hash: String = row.get("code_hash");
+        if password_matches(code, &code_hash)? {
+            // the used_at check stops two concurrent logins from spending the same code
+            let result = sqlx::query("UPDATE totp_backup_codes SET used_at = CURRENT_TIMESTAMP WHERE id = $1 AND used_at IS NULL")
+                .bind(row.get::<i64, _>("id"))
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+
+            return Ok(result.rows_affected() == 1);
+        }
+    }
+
+    Ok(false)
+}
+
+// helper function to reject changes dated in a month the user has closed
+pub(crate) async fn ensure_month_open(
+    pool: &sqlx::PgPool,
+    user_id: uuid::Uuid,
+    date: chrono::NaiveDate,
+) -> Result<(), (axum::http::StatusCode, String)> {
+    let closed: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM closed_months WHERE user_id = $1 AND month = $2)")
+        .bind(user_id)
+        .bind(month_start_of(date))
+        .fetch_one(pool)
+        .await
+        .map_err(db_error)?;
+
+    if closed {
+        return Err(closed_month_error());
+    }
+
+    Ok(())
+}
+
+// helper function to reject changes to existing transactions when any of them is dated in a closed month
+pub(crate) async fn ensure_transactions_open(
+    pool: &sqlx::PgPool,
+    user_id: uuid::Uuid,
+    ids: &[uuid::Uuid],
+) -> Result<(), (axum::http::StatusCode, String)> {
+    let closed: bool = sqlx::query_scalar(
+        "SELECT EXISTS (
+            SELECT 1
+            FROM transactions t
+            JOIN closed_months c ON c.user_id = t.user_id AND c.month = date_trunc('month', t.date)::date
+            WHERE t.user_id = $1 AND t.id = ANY($2)
+        )"
+    )
+    .bind(user_id)
+    .bind(ids)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error)?;
+
+    if closed {
+        return Err(closed_month_error());
+    }
+
+    Ok(())
+}
+
+// helper function to cap how many items a bulk request can carry, before any work is done on them
+pub(crate) fn ensure_bulk_size(items: usize, config: &crate::config::Config) -> Result<(), (axum::http::StatusCode, String)> {
+    if items > config.max_bulk_items {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            format!("Too many items in one request ({}), the limit is {}", items, config.max_bulk_items),
+        ));
+    }
+
+    Ok(())
+}
+
+// helper function to copy one of the user's transactions (and its embedding) to a new date, returning the copy
+// (None if the transaction doesn't exist or isn't the user's). the copy starts out cleared like a new transaction
+pub(crate) async fn copy_transaction(
+    conn: &mut sqlx::PgConnection,
+    user_id: uuid::Uuid,
+    transaction_id: uuid::Uuid,
+    date: chrono::NaiveDate,
+    config: &crate::config::Config,
+) -> Result<Option<Transaction>, (axum::http::StatusCode, String)> {
+    let row = sqlx::query(
+        "INSERT INTO transactions (user_id, amount, kind, category, date, description, source, exclude_from_budget, merchant, location)
+        SELECT user_id, amount, kind, category, $3, description, source, exclude_from_budget, merchant, location
+        FROM transactions
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at"
+    )
+    .bind(transaction_id)
+    .bind(user_id)
+    .bind(date)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let copy = transaction_from_row(&row, user_id, config);
+
+    // the embedding text doesn't depend on the date or amount, so the original's embedding can be reused as is
+    sqlx::query(
+        "INSERT INTO transaction_embeddings (transaction_id, user_id, embedding_text, embedding)
+        SELECT $1, user_id, embedding_text, embedding
+        FROM transaction_embeddings
+        WHERE transaction_id = $2"
+    )
+    .bind(copy.id)
+    .bind(transaction_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Some(copy))
+}
+
+// helper function for the error returned when a change touches a closed month
+fn closed_month_error() -> (axum::http::StatusCode, String) {
+    (axum::http::StatusCode::LOCKED, "Transactions in a closed month can't be changed, reopen the month first".to_string())
+}
+
+// helper function to sign a new access token for a user
+pub(crate) fn issue_access_token(state: &AppState, user_id: uuid::Uuid) -> Result<String, (axum::http::StatusCode, String)> {
+    // get the current time and compute the expiration time
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let exp = now + (state.config.effective_jwt_expiration_hours() * 3600); // convert hours to seconds
+
+    // create a claim for the user ID and expiration time
+    let claims = Claims {
+        sub: user_id.to_string(), // convert UUID to string for the JWT claim
+        exp: exp as usize, // expiration time as a unix timestamp
+        iat: Some(now as usize), // issued-at time, so clients can see how old their session is
+    };
+
+    // set our algorithm to HS256 (defaults to this regardless, but we set it explicitly for clarity)
+    let mut header = Header::new(Algorithm::HS256);
+
+    // stamp the standard token type, and the key id so verification knows which secret to use after a rotation
+    header.typ = Some("JWT".to_string());
+    header.kid = state.config.jwt_key_id.clone();
+
+    // get our secret key as an encoding key
+    let encoding_key = EncodingKey::from_secret(state.jwt_secret.as_bytes()); // convert the secret string to bytes for the encoding key
+
+    // encode the JWT
+    jsonwebtoken::encode(&header, &claims, &encoding_key)
+        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/* user information */
+
+// route for user registration
+pub(crate) async fn register_user(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
+    JsonBody(user_information): JsonBody<RegisterUser>
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    // throttle registrations per client IP to curb mass account creation
+    let ip = client_ip(&headers, connect_info.map(|info| info.0), state.config.trust_forwarded_for);
+    if !state.registration_limiter.check(ip) {
+        return Err((axum::http::StatusCode::TOO_MANY_REQUESTS, "Too many registrations, please try again later".to_string()));
+    }
+
+    // the same username rules as renaming later, so every account can re-save its own name
+    validate_username(&user_information.username)?;
+    ensure_max_length("email", &user_information.email, MAX_EMAIL_LENGTH)?;
+
+    // we use argon2 for password hashing
+    let password_hash = hash_password(&user_information.password)?;
+
     // now, we insert the user into the database
     sqlx::query!("INSERT into users (username, email, password_hash)
         VALUES ($1, $2, $3)",  
@@ -39,7 +587,7 @@ pub(crate) async fn register_user(
     )
     .execute(&state.pool)
     .await
-    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
     Ok(axum::http::StatusCode::CREATED)
 }
@@ -47,7 +595,7 @@ pub(crate) async fn register_user(
 // route for user login (verifying credentials)
 pub(crate) async fn user_login(
     axum::extract::State(state): axum::extract::State<AppState>,
-    axum::extract::Json(login_information): axum::extract::Json<LoginUser>
+    JsonBody(login_information): JsonBody<LoginUser>
 ) -> Result<axum::Json<LoginResponse>, (axum::http::StatusCode, String)> {
     // fetch the user from the database by username or email
 
@@ -57,7 +605,10 @@ pub(crate) async fn user_login(
     )
         .fetch_one(&state.pool)
         .await
-        .map_err(|_e| (axum::http::StatusCode::UNAUTHORIZED, "Invalid username/email or password".to_string()))?;
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => (axum::http::StatusCode::UNAUTHORIZED, "Invalid username/email or password".to_string()),
+            e => db_error(e),
+        })?;
 
     // verify the password
     let parsed_hash = argon2::PasswordHash::new(&user_record.password_hash)
@@ -67,28 +618,27 @@ pub(crate) async fn user_login(
         .verify_password(login_information.password.as_bytes(), &parsed_hash)
         .map_err(|_| (axum::http::StatusCode::UNAUTHORIZED, "Invalid username/email or password".to_string()))?;
 
+    // with two-factor auth turned on, the password alone isn't enough
+    let totp_secret: Option<Vec<u8>> = sqlx::query_scalar("SELECT totp_secret FROM users WHERE id = $1")
+        .bind(user_record.id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
 
-    // jwt generation
-
-    // get the current time and compute the expiration time
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-    let exp = now + (JWT_EXPIRATION_HOURS as u64 * 3600); // convert hours to seconds
-
-    // create a claim for the user ID and expiration time
-    let claims = Claims {
-        sub: user_record.id.to_string(), // convert UUID to string for the JWT claim
-        exp: exp as usize, // expiration time as a unix timestamp
-    };
+    if let Some(totp_secret) = totp_secret {
+        // tell the client to prompt for a code and retry
+        let code = login_information.totp_code
+            .filter(|code| !code.trim().is_empty())
+            .ok_or((axum::http::StatusCode::UNAUTHORIZED, "2fa_required".to_string()))?;
 
-    // set our algorithm to HS256 (defaults to this regardless, but we set it explicitly for clarity)
-    let header = Header::new(Algorithm::HS256);
+        if !second_factor_matches(&state.pool, user_record.id, &totp_secret, &code).await? {
+            return Err((axum::http::StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string()));
+        }
+    }
 
-    // get our secret key as an encoding key
-    let encoding_key = EncodingKey::from_secret(state.jwt_secret.as_bytes()); // convert the secret string to bytes for the encoding key
 
-    // encode the JWT
-    let token = jsonwebtoken::encode(&header, &claims, &encoding_key)
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    // jwt generation
+    let token = issue_access_token(&state, user_record.id)?;
     
     // make the response struct with the user ID and access token
     let response = axum::Json(LoginResponse {
@@ -99,112 +649,1470 @@ pub(crate) async fn user_login(
     Ok(response)
 }
 
+// route for swapping a still-valid token that's close to expiring for a fresh one, so active users stay logged in
+// tokens further from expiry than the renewal window are refused, and expired ones never get here
+pub(crate) async fn renew_token(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<LoginResponse>, (axum::http::StatusCode, String)> {
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let expires_in = (auth.expires_at as u64).saturating_sub(now);
+
+    if expires_in > state.config.token_renewal_window_secs {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "Token isn't due for renewal yet".to_string()));
+    }
+
+    let token = issue_access_token(&state, auth.user_id)?;
+
+    Ok(axum::Json(LoginResponse {
+        user_id: auth.user_id,
+        access_token: token,
+    }))
+}
+
+
+// route for describing the caller's current session, so clients don't have to decode the JWT themselves
+pub(crate) async fn get_session_info(
+    auth: AuthenticatedUser,
+) -> axum::Json<SessionInfo> {
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    axum::Json(SessionInfo {
+        user_id: auth.user_id,
+        issued_at: auth.issued_at,
+        expires_at: auth.expires_at,
+        expires_in: (auth.expires_at as u64).saturating_sub(now),
+    })
+}
+
+// route for checking a stored token is still valid (e.g. on app start) without touching any data
+pub(crate) async fn validate_token(
+    auth: AuthenticatedUser,
+) -> axum::Json<TokenValidation> {
+
+    axum::Json(TokenValidation {
+        valid: true,
+        user_id: auth.user_id,
+        expires_at: auth.expires_at,
+    })
+}
+
+// route for loading everything the frontend needs on startup in one call (profile, session, this month's
+// budgets and progress, and recent transactions), built from the same handlers the individual routes use
+pub(crate) async fn get_bootstrap(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<BootstrapResponse>, (axum::http::StatusCode, String)> {
+
+    let profile = sqlx::query(
+        "SELECT username, email, totp_secret IS NOT NULL AS two_factor_enabled, amount_scale, timezone,
+            monthly_summary_enabled, budget_alerts_enabled, summary_send_day
+        FROM users WHERE id = $1"
+    )
+        .bind(auth.user_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .map(|row| UserProfile {
+            user_id: auth.user_id,
+            username: row.get("username"),
+            email: row.get("email"),
+            two_factor_enabled: row.get("two_factor_enabled"),
+            amount_scale: row.get::<i16, _>("amount_scale") as u32,
+            timezone: row.get("timezone"),
+            monthly_summary_enabled: row.get("monthly_summary_enabled"),
+            budget_alerts_enabled: row.get("budget_alerts_enabled"),
+            summary_send_day: row.get::<i16, _>("summary_send_day") as u32,
+        })
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    let month = month_start_of(user_today(&state.pool, auth.user_id).await?);
+
+    let axum::Json(session) = get_session_info(auth.clone()).await;
+
+    let axum::Json(budgets) = get_budgets(
+        auth.clone(),
+        axum::extract::Query(BudgetQuery { month: Some(month) }),
+        axum::extract::State(state.clone()),
+    ).await?;
+
+    let axum::Json(budget_progress) = get_budget_progress(
+        auth.clone(),
+        axum::extract::Query(BudgetProgressQuery { month: Some(month), category: None, household_id: None }),
+        axum::extract::State(state.clone()),
+    ).await?;
+
+    let query = TransactionQuery { status: None, order_by: None, order_dir: None, modified_since: None };
+    let mut recent_transactions = list_transactions(&state, auth.user_id, &query).await?;
+    recent_transactions.truncate(BOOTSTRAP_RECENT_TRANSACTIONS);
+
+    Ok(axum::Json(BootstrapResponse {
+        profile,
+        session,
+        budgets,
+        budget_progress,
+        recent_transactions,
+    }))
+}
+
+// route for updating the authenticated user's profile (username, timezone and amount scale)
+// tokens carry the user id rather than the username, so existing tokens stay valid after a rename
+pub(crate) async fn update_current_user(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<UpdateUserRequest>
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    // check every field before changing anything, so a bad field doesn't leave the others half applied
+    let username = req.username.as_deref().map(str::trim);
+    if let Some(username) = username {
+        validate_username(username)?;
+    }
+
+    let timezone = req.timezone.as_deref().map(str::trim);
+    if let Some(timezone) = timezone {
+        let known: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+            .bind(timezone)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(db_error)?;
+        if !known {
+            return Err((axum::http::StatusCode::BAD_REQUEST, format!("Unknown timezone: {}", timezone)));
+        }
+    }
+
+    if req.amount_scale.is_some_and(|amount_scale| amount_scale > MAX_AMOUNT_SCALE) {
+        return Err((axum::http::StatusCode::BAD_REQUEST, format!("amount_scale must be between 0 and {}", MAX_AMOUNT_SCALE)));
+    }
+
+    if req.summary_send_day.is_some_and(|summary_send_day| !(1..=MAX_SUMMARY_SEND_DAY).contains(&summary_send_day)) {
+        return Err((axum::http::StatusCode::BAD_REQUEST, format!("summary_send_day must be between 1 and {}", MAX_SUMMARY_SEND_DAY)));
+    }
+
+    // then apply them all in one statement; a changed amount scale only applies to amounts entered from now on,
+    // and notification preferences only record what the user wants (nothing sends summaries or alerts yet)
+    // the unique constraint on username tells us if it's already taken
+    sqlx::query(
+        "UPDATE users SET
+            username = COALESCE($1, username),
+            timezone = COALESCE($2, timezone),
+            amount_scale = COALESCE($3, amount_scale),
+            monthly_summary_enabled = COALESCE($4, monthly_summary_enabled),
+            budget_alerts_enabled = COALESCE($5, budget_alerts_enabled),
+            summary_send_day = COALESCE($6, summary_send_day)
+        WHERE id = $7"
+    )
+    .bind(username)
+    .bind(timezone)
+    .bind(req.amount_scale.map(|scale| scale as i16))
+    .bind(req.monthly_summary_enabled)
+    .bind(req.budget_alerts_enabled)
+    .bind(req.summary_send_day.map(|day| day as i16))
+    .bind(auth.user_id)
+    .execute(&state.pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            (axum::http::StatusCode::CONFLICT, "Username is already taken".to_string())
+        }
+        e => db_error(e),
+    })?;
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// route for changing the authenticated user's password (rejects reuse of recent passwords)
+pub(crate) async fn change_password(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<ChangePasswordRequest>
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    let current_hash: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+        .bind(auth.user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    // the caller has to know their current password
+    if !password_matches(&req.current_password, &current_hash)? {
+        return Err((axum::http::StatusCode::UNAUTHORIZED, "Current password is incorrect".to_string()));
+    }
+
+    let history_length = state.config.password_history_length;
+
+    // reject the new password if it matches the current one or any in the user's history
+    if history_length > 0 {
+        let previous_hashes: Vec<String> = sqlx::query_scalar(
+            "SELECT password_hash FROM password_history WHERE user_id = $1 ORDER BY id DESC LIMIT $2"
+        )
+        .bind(auth.user_id)
+        .bind(history_length as i64)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+        for previous_hash in std::iter::once(&current_hash).chain(previous_hashes.iter()) {
+            if password_matches(&req.new_password, previous_hash)? {
+                return Err((
+                    axum::http::StatusCode::BAD_REQUEST,
+                    format!("New password must not match any of your last {} passwords", history_length),
+                ));
+            }
+        }
+    }
+
+    let new_hash = hash_password(&req.new_password)?;
+
+    // update the password and record the old one in a single database transaction
+    let mut db_tx = state.pool
+        .begin()
+        .await
+        .map_err(db_error)?;
+
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(&new_hash)
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+
+    if history_length > 0 {
+        sqlx::query("INSERT INTO password_history (user_id, password_hash) VALUES ($1, $2)")
+            .bind(auth.user_id)
+            .bind(&current_hash)
+            .execute(&mut *db_tx)
+            .await
+            .map_err(db_error)?;
+
+        // trim the history down to the configured length
+        sqlx::query(
+            "DELETE FROM password_history
+            WHERE user_id = $1
+            AND id NOT IN (
+                SELECT id FROM password_history WHERE user_id = $1 ORDER BY id DESC LIMIT $2
+            )"
+        )
+        .bind(auth.user_id)
+        .bind(history_length as i64)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+    }
+
+    db_tx
+        .commit()
+        .await
+        .map_err(db_error)?;
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// route for clearing the authenticated user's data while keeping their account, login and settings
+// transactions (live and archived), budgets and spending limits go, and closed months are reopened so the
+// user can start entering from scratch; category rules, preferences and household memberships stay
+pub(crate) async fn reset_user_data(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<ResetDataRequest>
+) -> Result<axum::Json<ResetDataResponse>, (axum::http::StatusCode, String)> {
+
+    let password_hash: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+        .bind(auth.user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    if !password_matches(&req.password, &password_hash)? {
+        return Err((axum::http::StatusCode::UNAUTHORIZED, "Password is incorrect".to_string()));
+    }
+
+    // all or nothing, so a failure part way through doesn't leave half the data behind
+    let mut db_tx = state.pool.begin().await.map_err(db_error)?;
+
+    // embeddings go with their transactions (ON DELETE CASCADE), and sync clients get tombstones for them
+    let transactions = sqlx::query("DELETE FROM transactions WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+    let archived_transactions = sqlx::query("DELETE FROM archived_transactions WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+    let budgets = sqlx::query("DELETE FROM budgets WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+    sqlx::query("DELETE FROM monthly_limits WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+
+    sqlx::query("DELETE FROM closed_months WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+
+    db_tx.commit().await.map_err(db_error)?;
+
+    Ok(axum::Json(ResetDataResponse { transactions, archived_transactions, budgets }))
+}
+
+
+// route for turning on two-factor auth, returning the secret and one-time backup codes
+// it takes effect immediately, so the backup codes are how a user gets back in if the secret never made it into their app
+pub(crate) async fn enable_two_factor(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<TwoFactorSetup>, (axum::http::StatusCode, String)> {
+
+    let username: String = sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
+        .bind(auth.user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    // 160 bits, the key length RFC 4226 recommends for HMAC-SHA1
+    let secret: [u8; 20] = rand::random();
+
+    // backup codes are longer than TOTP codes, so the two can never be confused
+    let backup_codes: Vec<String> = (0..TOTP_BACKUP_CODE_COUNT)
+        .map(|_| format!("{:010}", rand::random::<u64>() % 10_000_000_000))
+        .collect();
+
+    let mut db_tx = state.pool
+        .begin()
+        .await
+        .map_err(db_error)?;
+
+    // only set the secret if there isn't one already, so an enabled user can't be silently re-keyed
+    let result = sqlx::query("UPDATE users SET totp_secret = $1 WHERE id = $2 AND totp_secret IS NULL")
+        .bind(&secret[..])
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((axum::http::StatusCode::CONFLICT, "Two-factor auth is already enabled".to_string()));
+    }
+
+    sqlx::query("DELETE FROM totp_backup_codes WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+
+    for code in &backup_codes {
+        sqlx::query("INSERT INTO totp_backup_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(auth.user_id)
+            .bind(hash_password(code)?)
+            .execute(&mut *db_tx)
+            .await
+            .map_err(db_error)?;
+    }
+
+    db_tx
+        .commit()
+        .await
+        .map_err(db_error)?;
+
+    Ok(axum::Json(TwoFactorSetup {
+        secret: base32_encode(&secret),
+        otpauth_uri: provisioning_uri(&secret, &username),
+        backup_codes,
+    }))
+}
+
+// route for turning off two-factor auth, which needs a valid code so a stolen token alone can't do it
+pub(crate) async fn disable_two_factor(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<TwoFactorCodeRequest>
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    let totp_secret: Option<Vec<u8>> = sqlx::query_scalar("SELECT totp_secret FROM users WHERE id = $1")
+        .bind(auth.user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let Some(totp_secret) = totp_secret else {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "Two-factor auth is not enabled".to_string()));
+    };
+
+    if !second_factor_matches(&state.pool, auth.user_id, &totp_secret, &req.code).await? {
+        return Err((axum::http::StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string()));
+    }
+
+    let mut db_tx = state.pool
+        .begin()
+        .await
+        .map_err(db_error)?;
+
+    sqlx::query("UPDATE users SET totp_secret = NULL WHERE id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+
+    sqlx::query("DELETE FROM totp_backup_codes WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *db_tx)
+        .await
+        .map_err(db_error)?;
+
+    db_tx
+        .commit()
+        .await
+        .map_err(db_error)?;
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+/* transactions */
+
+// route for adding a transaction
+pub(crate) async fn add_transaction(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(mut req): JsonBody<AddTransactionRequest>
+) -> Result<(axum::http::StatusCode, axum::http::HeaderMap), (axum::http::StatusCode, String)> {
+
+    // convert the TransactionKind to a string for storage
+    let transaction_type = transaction_kind_str(&req.kind);
+
+    // transactions are cleared unless the client says otherwise
+    let transaction_status = match req.status {
+        Some(TransactionStatus::Pending) => "pending",
+        Some(TransactionStatus::Cleared) | None => "cleared",
+    };
+
+    // an income source only makes sense on income transactions
+    if req.source.is_some() && matches!(req.kind, TransactionKind::Expense) {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "source can only be set on income transactions".to_string()));
+    }
+
+    ensure_amount_fits(req.amount, user_amount_scale(&state.pool, auth.user_id).await?)?;
+    ensure_max_length("category", req.category.as_deref().unwrap_or(""), MAX_CATEGORY_LENGTH)?;
+    ensure_max_length("description", req.description.as_deref().unwrap_or(""), MAX_DESCRIPTION_LENGTH)?;
+    ensure_max_length("merchant", req.merchant.as_deref().unwrap_or(""), MAX_MERCHANT_LENGTH)?;
+    ensure_max_length("location", req.location.as_deref().unwrap_or(""), MAX_LOCATION_LENGTH)?;
+
+    // manual entries are nearly always for today, so the date can be left out
+    let date = match req.date {
+        Some(date) => date,
+        None => user_today(&state.pool, auth.user_id).await?,
+    };
+
+    ensure_not_future(&state, auth.user_id, date).await?;
+    ensure_month_open(&state.pool, auth.user_id, date).await?;
+
+    // fill in a missing category from the user's category rules (before the embedding, so it includes the category)
+    if req.category.as_deref().is_none_or(|category| category.trim().is_empty())
+        && let Some(description) = req.description.as_deref()
+        && let Some(category) = rule_category_for(&state.pool, auth.user_id, description).await?
+    {
+        req.category = Some(category);
+    }
+
+    // with limits enforced, a hard cap blocks the expense and a soft limit just flags it in a response header
+    let exclude_from_budget = req.exclude_from_budget.unwrap_or(false);
+    let mut headers = axum::http::HeaderMap::new();
+    if state.config.enforce_spending_limits && matches!(req.kind, TransactionKind::Expense) && !exclude_from_budget {
+        let warnings = check_spending_limits(&state, auth.user_id, None, req.category.as_deref(), date, req.amount).await?;
+        if !warnings.is_empty() {
+            let value = axum::http::HeaderValue::from_str(&warnings.join(", ")).expect("scope names are valid header values");
+            headers.insert(SPENDING_WARNING_HEADER, value);
+        }
+    }
+
+    // insert the transaction into the database (with the description and merchant encrypted if ENCRYPT_NOTES is on)
+    let transaction_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT into transactions (user_id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING id"
+    )
+    .bind(auth.user_id)
+    .bind(req.amount)
+    .bind(transaction_type)
+    .bind(&req.category)
+    .bind(date)
+    .bind(seal_note(&state.config, auth.user_id, req.description.as_deref()))
+    .bind(&req.source)
+    .bind(transaction_status)
+    .bind(exclude_from_budget)
+    .bind(seal_note(&state.config, auth.user_id, req.merchant.as_deref()))
+    .bind(&req.location)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    // now we call our embedding generation function to generate an embedding for this transaction
+    let embedding_text = req.transaction_string_embedding();
+
+    let embedding = generate_transaction_embedding(&state, &embedding_text).await?;
+
+    // store the embedding in the database linked to this transaction
+    store_transaction_embedding(&state, transaction_id, auth.user_id, &embedding_text, embedding).await?;
+
+    Ok((axum::http::StatusCode::CREATED, headers))
+}
+
+
+// route for editing one of the caller's transactions, with the same body as adding one
+// (a left out date, status or exclude_from_budget keeps the transaction's current one, rather than resetting it)
+pub(crate) async fn update_transaction(
+    auth: AuthenticatedUser,
+    axum::extract::Path(transaction_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<AddTransactionRequest>
+) -> Result<(axum::http::HeaderMap, axum::Json<Transaction>), (axum::http::StatusCode, String)> {
+
+    let transaction_type = transaction_kind_str(&req.kind);
+    let transaction_status = req.status.as_ref().map(|status| match status {
+        TransactionStatus::Pending => "pending",
+        TransactionStatus::Cleared => "cleared",
+    });
+
+    // same rules as adding a transaction
+    if req.source.is_some() && matches!(req.kind, TransactionKind::Expense) {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "source can only be set on income transactions".to_string()));
+    }
+
+    ensure_amount_fits(req.amount, user_amount_scale(&state.pool, auth.user_id).await?)?;
+    ensure_max_length("category", req.category.as_deref().unwrap_or(""), MAX_CATEGORY_LENGTH)?;
+    ensure_max_length("description", req.description.as_deref().unwrap_or(""), MAX_DESCRIPTION_LENGTH)?;
+    ensure_max_length("merchant", req.merchant.as_deref().unwrap_or(""), MAX_MERCHANT_LENGTH)?;
+    ensure_max_length("location", req.location.as_deref().unwrap_or(""), MAX_LOCATION_LENGTH)?;
+
+    // neither the month it's in now nor the one it's moving to can be closed
+    ensure_transactions_open(&state.pool, auth.user_id, &[transaction_id]).await?;
+    if let Some(date) = req.date {
+        ensure_not_future(&state, auth.user_id, date).await?;
+        ensure_month_open(&state.pool, auth.user_id, date).await?;
+    }
+
+    // the edited expense goes through the same limits as a new one, checked against its date and flag once the
+    // edit is applied (an unknown transaction falls through to the 404 below)
+    let mut headers = axum::http::HeaderMap::new();
+    if state.config.enforce_spending_limits && matches!(req.kind, TransactionKind::Expense) {
+        let current = sqlx::query("SELECT date, exclude_from_budget FROM transactions WHERE id = $1 AND user_id = $2")
+            .bind(transaction_id)
+            .bind(auth.user_id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?;
+
+        if let Some(current) = current {
+            let date = req.date.unwrap_or_else(|| current.get("date"));
+            if !req.exclude_from_budget.unwrap_or_else(|| current.get("exclude_from_budget")) {
+                let warnings = check_spending_limits(&state, auth.user_id, Some(transaction_id), req.category.as_deref(), date, req.amount).await?;
+                if !warnings.is_empty() {
+                    let value = axum::http::HeaderValue::from_str(&warnings.join(", ")).expect("scope names are valid header values");
+                    headers.insert(SPENDING_WARNING_HEADER, value);
+                }
+            }
+        }
+    }
+
+    // only the owner can edit a transaction (with the description and merchant encrypted if ENCRYPT_NOTES is on)
+    let row = sqlx::query(
+        "UPDATE transactions
+        SET amount = $1, kind = $2, category = $3, date = COALESCE($4, date), description = $5, source = $6,
+            status = COALESCE($7, status), exclude_from_budget = COALESCE($8, exclude_from_budget), merchant = $9, location = $10
+        WHERE id = $11 AND user_id = $12
+        RETURNING id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at"
+    )
+    .bind(req.amount)
+    .bind(transaction_type)
+    .bind(&req.category)
+    .bind(req.date)
+    .bind(seal_note(&state.config, auth.user_id, req.description.as_deref()))
+    .bind(&req.source)
+    .bind(transaction_status)
+    .bind(req.exclude_from_budget)
+    .bind(seal_note(&state.config, auth.user_id, req.merchant.as_deref()))
+    .bind(&req.location)
+    .bind(transaction_id)
+    .bind(auth.user_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?
+    .ok_or((axum::http::StatusCode::NOT_FOUND, "Transaction not found".to_string()))?;
+
+    // re-embed only when the text the embedding was made from has changed (so fixing an amount or date doesn't
+    // call out to OpenAI)
+    let embedding_text = req.transaction_string_embedding();
+    let stored_text: Option<String> = sqlx::query_scalar("SELECT embedding_text FROM transaction_embeddings WHERE transaction_id = $1")
+        .bind(transaction_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    if stored_text.is_some() && open_note(&state.config, auth.user_id, stored_text).as_deref() != Some(embedding_text.as_str()) {
+        let embedding = generate_transaction_embedding(&state, &embedding_text).await?;
+
+        sqlx::query("DELETE FROM transaction_embeddings WHERE transaction_id = $1")
+            .bind(transaction_id)
+            .execute(&state.pool)
+            .await
+            .map_err(db_error)?;
+        store_transaction_embedding(&state, transaction_id, auth.user_id, &embedding_text, embedding).await?;
+    }
+
+    Ok((headers, axum::Json(transaction_from_row(&row, auth.user_id, &state.config))))
+}
+
+// route for getting transactions for authenticated user (optionally filtered by status)
+// with modified_since it instead returns what changed after that time, oldest change first, including tombstones
+// for deleted transactions, so offline clients can sync incrementally
+pub(crate) async fn get_transactions(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<TransactionQuery>,
+    signed: SignedAmounts,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    if let Some(since) = query.modified_since {
+        let mut changes = transaction_changes_since(&state, auth.user_id, since).await?;
+        for change in changes.iter_mut() {
+            if let TransactionChange::Changed(transaction) = change {
+                signed.apply(transaction);
+            }
+        }
+        return Ok(axum::Json(changes).into_response());
+    }
+
+    let mut transactions = list_transactions(&state, auth.user_id, &query).await?;
+    signed.apply_all(&mut transactions);
+    Ok(axum::Json(transactions).into_response())
+}
+
+// helper function to list a user's transactions, filtered and sorted as the query asks
+pub(crate) async fn list_transactions(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    query: &TransactionQuery,
+) -> Result<Vec<Transaction>, (axum::http::StatusCode, String)> {
+
+    let status_filter = query.status.as_ref().map(|status| match status {
+        TransactionStatus::Pending => "pending",
+        TransactionStatus::Cleared => "cleared",
+    });
+
+    // the sort column and direction only ever come from these fixed strings, never from the raw query
+    let order_column = match query.order_by {
+        Some(TransactionOrderBy::Date) | None => "date",
+        Some(TransactionOrderBy::Amount) => "amount",
+        Some(TransactionOrderBy::CreatedAt) => "created_at",
+    };
+    let order_direction = match query.order_dir {
+        Some(SortDirection::Asc) => "ASC",
+        Some(SortDirection::Desc) | None => "DESC",
+    };
+
+    // fetch the user's transactions from the database (id breaks ties so the order is stable)
+    let sql = format!(
+        "SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at
+        FROM transactions
+        WHERE user_id = $1
+        AND ($2::text IS NULL OR status = $2)
+        ORDER BY {} {}, id {}",
+        order_column, order_direction, order_direction
+    );
+
+    let rows = sqlx::query(&sql)
+        .bind(user_id)
+        .bind(status_filter)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    // map the transactions from the database into Transaction structs
+    let result: Vec<Transaction> = rows
+        .iter()
+        .map(|row| transaction_from_row(row, user_id, &state.config))
+        .collect();
+
+    Ok(result)
+}
+
+// helper function to collect the user's transactions changed after `since`, plus tombstones for ones deleted after it
+async fn transaction_changes_since(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<TransactionChange>, (axum::http::StatusCode, String)> {
+
+    let changed = sqlx::query(
+        "SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at
+        FROM transactions
+        WHERE user_id = $1 AND updated_at > $2"
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let deleted = sqlx::query("SELECT id, deleted_at FROM transaction_tombstones WHERE user_id = $1 AND deleted_at > $2")
+        .bind(user_id)
+        .bind(since)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let mut changes: Vec<(chrono::DateTime<chrono::Utc>, TransactionChange)> = changed
+        .iter()
+        .map(|row| {
+            let transaction = transaction_from_row(row, user_id, &state.config);
+            (transaction.updated_at, TransactionChange::Changed(transaction))
+        })
+        .chain(deleted.iter().map(|row| {
+            let deleted_at = row.get("deleted_at");
+            (deleted_at, TransactionChange::Deleted(DeletedTransaction { id: row.get("id"), deleted: true, deleted_at }))
+        }))
+        .collect();
+    changes.sort_by_key(|(at, _)| *at);
+
+    Ok(changes.into_iter().map(|(_, change)| change).collect())
+}
+
+// route for the dates of the user's first and last transactions, and how many there are
+// (archived transactions aren't included, matching the transaction list)
+pub(crate) async fn get_transaction_range(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<TransactionRange>, (axum::http::StatusCode, String)> {
+
+    let row = sqlx::query("SELECT MIN(date) AS earliest, MAX(date) AS latest, COUNT(*) AS count FROM transactions WHERE user_id = $1")
+        .bind(auth.user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(axum::Json(TransactionRange {
+        earliest: row.get("earliest"),
+        latest: row.get("latest"),
+        count: row.get("count"),
+    }))
+}
+
+// route for browsing the authenticated user's archived transactions (moved there by the archival job)
+pub(crate) async fn get_archived_transactions(
+    auth: AuthenticatedUser,
+    signed: SignedAmounts,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<Transaction>>, (axum::http::StatusCode, String)> {
+
+    // newest first, with the latest entered and then id breaking ties so the order is stable
+    let rows = sqlx::query(
+        "SELECT id, amount, kind, category, date, description, source, status, exclude_from_budget, merchant, location, seq, updated_at
+        FROM archived_transactions
+        WHERE user_id = $1
+        ORDER BY date DESC, created_at DESC, id DESC"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let mut result: Vec<Transaction> = rows
+        .iter()
+        .map(|row| transaction_from_row(row, auth.user_id, &state.config))
+        .collect();
+    signed.apply_all(&mut result);
+
+    Ok(axum::Json(result))
+}
+
+// route for marking a pending transaction as cleared
+pub(crate) async fn clear_transaction(
+    auth: AuthenticatedUser,
+    axum::extract::Path(transaction_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    ensure_transactions_open(&state.pool, auth.user_id, &[transaction_id]).await?;
+
+    // only the owner can clear a transaction (clearing one that's already cleared is a no-op)
+    let result = sqlx::query(
+        "UPDATE transactions SET status = 'cleared' WHERE id = $1 AND user_id = $2"
+    )
+    .bind(transaction_id)
+    .bind(auth.user_id)
+    .execute(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((axum::http::StatusCode::NOT_FOUND, "Transaction not found".to_string()));
+    }
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// route for deleting one of the caller's transactions (another user's transaction is a 404, same as a missing one)
+pub(crate) async fn delete_transaction(
+    auth: AuthenticatedUser,
+    axum::extract::Path(transaction_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    ensure_transactions_open(&state.pool, auth.user_id, &[transaction_id]).await?;
+
+    let result = sqlx::query("DELETE FROM transactions WHERE id = $1 AND user_id = $2")
+        .bind(transaction_id)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((axum::http::StatusCode::NOT_FOUND, "Transaction not found".to_string()));
+    }
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// route for copying one of the caller's transactions to a new date (today unless the body says otherwise)
+pub(crate) async fn duplicate_transaction(
+    auth: AuthenticatedUser,
+    axum::extract::Path(transaction_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    req: Result<JsonBody<DuplicateTransactionRequest>, (axum::http::StatusCode, String)>
+) -> Result<(axum::http::StatusCode, axum::Json<Transaction>), (axum::http::StatusCode, String)> {
+
+    let date = match req {
+        Ok(JsonBody(req)) => req.date,
+        // no body at all (so no Content-Type) just means the copy is dated today
+        Err((status, _)) if status == axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE => None,
+        Err(e) => return Err(e),
+    };
+    let date = match date {
+        Some(date) => date,
+        None => user_today(&state.pool, auth.user_id).await?,
+    };
+
+    ensure_not_future(&state, auth.user_id, date).await?;
+    ensure_month_open(&state.pool, auth.user_id, date).await?;
+
+    let mut db_tx = state.pool
+        .begin()
+        .await
+        .map_err(db_error)?;
+
+    let duplicate = copy_transaction(&mut db_tx, auth.user_id, transaction_id, date, &state.config)
+        .await?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "Transaction not found".to_string()))?;
+
+    db_tx
+        .commit()
+        .await
+        .map_err(db_error)?;
+
+    Ok((axum::http::StatusCode::CREATED, axum::Json(duplicate)))
+}
+
+// route for copying every transaction in one month into another, keeping each one's day of the month
+// days past the end of a shorter target month are clamped to its last day (e.g. Jan 31 -> Feb 28) and reported
+pub(crate) async fn clone_month(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<CloneMonthRequest>
+) -> Result<(axum::http::StatusCode, axum::Json<CloneMonthResponse>), (axum::http::StatusCode, String)> {
+    use chrono::Datelike;
+
+    let from_month = month_start_of(req.from_month);
+    let to_month = month_start_of(req.to_month);
+
+    if from_month == to_month {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "from_month and to_month must be different months".to_string()));
+    }
+
+    ensure_month_open(&state.pool, auth.user_id, to_month).await?;
+
+    let sources = sqlx::query("SELECT id, date FROM transactions WHERE user_id = $1 AND date >= $2 AND date < $3 ORDER BY date ASC, id ASC")
+        .bind(auth.user_id)
+        .bind(from_month)
+        .bind(next_month_start_of(from_month))
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    ensure_bulk_size(sources.len(), &state.config)?;
+
+    // work out every copy's date up front, so the whole clone can be refused if any would land in the future
+    let last_day = next_month_start_of(to_month).pred_opt().unwrap().day();
+    let copies: Vec<(uuid::Uuid, chrono::NaiveDate, chrono::NaiveDate)> = sources
+        .iter()
+        .map(|source| {
+            let original: chrono::NaiveDate = source.get("date");
+            (source.get("id"), original, to_month.with_day(original.day().min(last_day)).unwrap())
+        })
+        .collect();
+
+    if let Some(latest) = copies.iter().map(|(_, _, date)| *date).max() {
+        ensure_not_future(&state, auth.user_id, latest).await?;
+    }
+
+    let mut clamped = Vec::new();
+
+    // copy everything in one database transaction, so a failure part way leaves the target month untouched
+    let mut db_tx = state.pool
+        .begin()
+        .await
+        .map_err(db_error)?;
+
+    for (id, original, date) in copies {
+        if date.day() != original.day() {
+            clamped.push(ClampedDate { original, date });
+        }
+
+        copy_transaction(&mut db_tx, auth.user_id, id, date, &state.config).await?;
+    }
+
+    db_tx
+        .commit()
+        .await
+        .map_err(db_error)?;
+
+    Ok((
+        axum::http::StatusCode::CREATED,
+        axum::Json(CloneMonthResponse { created: sources.len(), clamped }),
+    ))
+}
+
+// route for recategorizing a batch of transactions in one query
+pub(crate) async fn recategorize_transactions(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<RecategorizeRequest>
+) -> Result<axum::Json<RecategorizeResponse>, (axum::http::StatusCode, String)> {
+
+    if req.ids.is_empty() {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "ids must not be empty".to_string()));
+    }
+    ensure_bulk_size(req.ids.len(), &state.config)?;
+    ensure_max_length("category", req.category.as_deref().unwrap_or(""), MAX_CATEGORY_LENGTH)?;
+
+    // drop duplicate ids so the row count below can be compared against the number of ids
+    let mut ids = req.ids;
+    ids.sort();
+    ids.dedup();
+
+    ensure_transactions_open(&state.pool, auth.user_id, &ids).await?;
+
+    // run the update inside a database transaction so we can roll it back if any id isn't the caller's
+    let mut db_tx = state.pool
+        .begin()
+        .await
+        .map_err(db_error)?;
+
+    let result = sqlx::query(
+        "UPDATE transactions SET category = $1 WHERE user_id = $2 AND id = ANY($3)"
+    )
+    .bind(&req.category)
+    .bind(auth.user_id)
+    .bind(&ids)
+    .execute(&mut *db_tx)
+    .await
+    .map_err(db_error)?;
+
+    // every id has to belong to the caller, otherwise nothing is changed
+    // (dropping db_tx without committing rolls the update back)
+    if result.rows_affected() != ids.len() as u64 {
+        return Err((axum::http::StatusCode::NOT_FOUND, "One or more transactions were not found".to_string()));
+    }
+
+    db_tx
+        .commit()
+        .await
+        .map_err(db_error)?;
+
+    Ok(axum::Json(RecategorizeResponse { updated: result.rows_affected() }))
+}
+
+// route for running the user's category rules over their existing transactions (uncategorized ones unless "all" is set)
+// each transaction gets the category of its first matching rule; transactions in closed months are left alone
+pub(crate) async fn apply_category_rules(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    req: Result<JsonBody<ApplyRulesRequest>, (axum::http::StatusCode, String)>
+) -> Result<axum::Json<RecategorizeResponse>, (axum::http::StatusCode, String)> {
+
+    let all = match req {
+        Ok(JsonBody(req)) => req.all,
+        // no body at all (so no Content-Type) just means the defaults
+        Err((status, _)) if status == axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE => false,
+        Err(e) => return Err(e),
+    };
+
+    // rules in the order they apply: the first one (by priority) that matches a transaction wins
+    let rules: Vec<(String, String)> = sqlx::query(
+        "SELECT match_contains, category FROM category_rules WHERE user_id = $1 ORDER BY priority ASC, created_at ASC"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?
+    .iter()
+    .map(|row| (row.get::<String, _>("match_contains").to_lowercase(), row.get("category")))
+    .collect();
+
+    // the matching is done here rather than in SQL, since descriptions may be stored encrypted
+    let candidates = sqlx::query(
+        "SELECT t.id, t.description, t.category
+        FROM transactions t
+        WHERE t.user_id = $1
+        AND t.description IS NOT NULL
+        AND ($2 OR t.category IS NULL OR TRIM(t.category) = '')
+        AND NOT EXISTS (
+            SELECT 1 FROM closed_months c
+            WHERE c.user_id = t.user_id AND c.month = date_trunc('month', t.date)::date
+        )"
+    )
+    .bind(auth.user_id)
+    .bind(all)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let mut ids: Vec<uuid::Uuid> = Vec::new();
+    let mut categories: Vec<String> = Vec::new();
+    for row in &candidates {
+        let description = open_note(&state.config, auth.user_id, row.get("description")).unwrap_or_default().to_lowercase();
+        let current: Option<String> = row.get("category");
+
+        if let Some((_, category)) = rules.iter().find(|(match_contains, _)| description.contains(match_contains.as_str()))
+            && current.as_deref() != Some(category.as_str())
+        {
+            ids.push(row.get("id"));
+            categories.push(category.clone());
+        }
+    }
+
+    let result = sqlx::query(
+        "UPDATE transactions t
+        SET category = matched.category
+        FROM UNNEST($2::uuid[], $3::text[]) AS matched(id, category)
+        WHERE t.id = matched.id AND t.user_id = $1"
+    )
+    .bind(auth.user_id)
+    .bind(&ids)
+    .bind(&categories)
+    .execute(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(axum::Json(RecategorizeResponse { updated: result.rows_affected() }))
+}
+
+// route for deleting many transactions at once, by a list of ids or by a date range/category filter
+pub(crate) async fn bulk_delete_transactions(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<BulkDeleteRequest>
+) -> Result<axum::Json<BulkDeleteResponse>, (axum::http::StatusCode, String)> {
+
+    if !req.confirm {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "Bulk delete requires \"confirm\": true".to_string()));
+    }
+
+    let has_filter = req.from.is_some() || req.to.is_some() || req.category.is_some();
+
+    let result = match req.ids {
+        // delete by id (ids the caller doesn't own are simply not matched)
+        Some(ids) => {
+            if has_filter {
+                return Err((axum::http::StatusCode::BAD_REQUEST, "Provide either ids or a filter, not both".to_string()));
+            }
+            ensure_bulk_size(ids.len(), &state.config)?;
+
+            ensure_transactions_open(&state.pool, auth.user_id, &ids).await?;
+
+            sqlx::query("DELETE FROM transactions WHERE user_id = $1 AND id = ANY($2)")
+                .bind(auth.user_id)
+                .bind(&ids)
+                .execute(&state.pool)
+                .await
+        }
+        // delete by filter, refusing an empty filter so this can't wipe every transaction
+        None => {
+            if !has_filter {
+                return Err((axum::http::StatusCode::BAD_REQUEST, "Provide ids or at least one of from, to, category".to_string()));
+            }
+
+            // refuse the whole delete if the filter reaches into a closed month
+            let touches_closed_month: bool = sqlx::query_scalar(
+                "SELECT EXISTS (
+                    SELECT 1
+                    FROM transactions t
+                    JOIN closed_months c ON c.user_id = t.user_id AND c.month = date_trunc('month', t.date)::date
+                    WHERE t.user_id = $1
+                    AND ($2::date IS NULL OR t.date >= $2)
+                    AND ($3::date IS NULL OR t.date <= $3)
+                    AND ($4::text IS NULL OR t.category = $4)
+                )"
+            )
+            .bind(auth.user_id)
+            .bind(req.from)
+            .bind(req.to)
+            .bind(&req.category)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(db_error)?;
+
+            if touches_closed_month {
+                return Err(closed_month_error());
+            }
+
+            sqlx::query(
+                "DELETE FROM transactions
+                WHERE user_id = $1
+                AND ($2::date IS NULL OR date >= $2)
+                AND ($3::date IS NULL OR date <= $3)
+                AND ($4::text IS NULL OR category = $4)"
+            )
+            .bind(auth.user_id)
+            .bind(req.from)
+            .bind(req.to)
+            .bind(&req.category)
+            .execute(&state.pool)
+            .await
+        }
+    }
+    .map_err(db_error)?;
+
+    Ok(axum::Json(BulkDeleteResponse { deleted: result.rows_affected() }))
+}
+
+/* budgets */
+
+// route for creating/updating a budget (upsert)
+pub(crate) async fn upsert_budget(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<UpsertBudgetRequest>
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    // a blank category would never match any transaction in the progress join
+    let category = req.category.trim();
+    if category.is_empty() {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "category must not be empty".to_string()));
+    }
+    ensure_max_length("category", category, MAX_CATEGORY_LENGTH)?;
+    // budgets are always whole cents (their column is still NUMERIC(15, 2)), and so are their limits
+    ensure_amount_fits(req.amount, DEFAULT_AMOUNT_SCALE)?;
+    ensure_limits_valid(&req.limits)?;
+    ensure_budget_month_editable(&state, auth.user_id, req.month).await?;
+
+    // with a cap on categories per month, only updates to categories already budgeted are allowed once it's reached
+    // (the month stays locked from the first count to the commit, so concurrent new categories can't both squeeze in)
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    lock_budget_month(&mut tx, auth.user_id, req.month).await?;
+    let categories_before = count_budget_categories(&mut tx, auth.user_id, req.month).await?;
+
+    // insert the budget into the database (or update if it already exists)
+    sqlx::query(
+        "INSERT INTO budgets (user_id, month, category, amount, warn_at, cap_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (user_id, month, category)
+         DO UPDATE SET amount = EXCLUDED.amount, warn_at = EXCLUDED.warn_at, cap_at = EXCLUDED.cap_at, updated_at = CURRENT_TIMESTAMP"
+    )
+    .bind(auth.user_id)
+    .bind(req.month)
+    .bind(category)
+    .bind(req.amount)
+    .bind(req.limits.warn_at)
+    .bind(req.limits.cap_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let categories_after = count_budget_categories(&mut tx, auth.user_id, req.month).await?;
+    ensure_within_budget_category_cap(&state.config, categories_before, categories_after)?;
+    tx.commit().await.map_err(db_error)?;
 
-/* transactions */
+    Ok(axum::http::StatusCode::CREATED)
+}
 
-// route for adding a transaction
-pub(crate) async fn add_transaction(
+// route for copying a month's budgets into another month
+// existing budgets in the target month are never overwritten, so running it twice is harmless
+pub(crate) async fn copy_budgets(
     auth: AuthenticatedUser,
     axum::extract::State(state): axum::extract::State<AppState>,
-    axum::extract::Json(req): axum::extract::Json<AddTransactionRequest>
-) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+    JsonBody(req): JsonBody<CopyBudgetsRequest>
+) -> Result<axum::Json<CopyBudgetsResponse>, (axum::http::StatusCode, String)> {
 
-    // convert the TransactionKind to a string for storage
-    let transaction_type = match req.kind {
-        TransactionKind::Income => "income",
-        TransactionKind::Expense => "expense",
+    let target_month = month_start_of(req.month);
+    let source_month = match req.from {
+        Some(from) => month_start_of(from),
+        None => month_start_of(target_month.pred_opt().unwrap()),
     };
 
-    // insert the transaction into the database
-    let inserted_transaction = sqlx::query!("INSERT into transactions (user_id, amount, kind, category, date, description)
-        VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
-        auth.user_id,
-        req.amount,
-        transaction_type,
-        req.category,
-        req.date,
-        req.description
+    if source_month == target_month {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "Can't copy budgets into the same month".to_string()));
+    }
+    ensure_budget_month_editable(&state, auth.user_id, target_month).await?;
+
+    // copy inside a database transaction, so it can be undone if it takes the month past the category cap
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    lock_budget_month(&mut tx, auth.user_id, target_month).await?;
+    let categories_before = count_budget_categories(&mut tx, auth.user_id, target_month).await?;
+
+    // count the source budgets alongside what was actually inserted, so the rest were skipped
+    let row = sqlx::query(
+        "WITH source AS (
+            SELECT category, amount, warn_at, cap_at FROM budgets WHERE user_id = $1 AND month = $2
+        ),
+        inserted AS (
+            INSERT INTO budgets (user_id, month, category, amount, warn_at, cap_at)
+            SELECT $1, $3, category, amount, warn_at, cap_at FROM source
+            ON CONFLICT (user_id, month, category) DO NOTHING
+            RETURNING 1
+        )
+        SELECT
+            (SELECT COUNT(*) FROM source) AS total,
+            (SELECT COUNT(*) FROM inserted) AS copied"
     )
-    .fetch_one(&state.pool)
+    .bind(auth.user_id)
+    .bind(source_month)
+    .bind(target_month)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
-    let transaction_id = inserted_transaction.id;
+    let total: i64 = row.get("total");
+    let copied: i64 = row.get("copied");
 
-    // now we call our embedding generation function to generate an embedding for this transaction
-    let embedding_text = req.transaction_string_embedding();
-
-    let embedding = generate_transaction_embedding(&state, &embedding_text).await?;
+    ensure_within_budget_category_cap(&state.config, categories_before, categories_before + copied)?;
+    tx.commit().await.map_err(db_error)?;
 
-    // store the embedding in the database linked to this transaction
-    store_transaction_embedding(&state, transaction_id, auth.user_id, &embedding_text, embedding).await?;
-
-    Ok(axum::http::StatusCode::CREATED)
+    Ok(axum::Json(CopyBudgetsResponse {
+        copied,
+        skipped_existing: total - copied,
+    }))
 }
 
-
-// route for getting transactions for authenticated user
-pub(crate) async fn get_transactions(
+// route for setting a month's budgets to each category's average monthly spend over the N months before it
+// (the target month defaults to the current one). categories with no spend in that window are skipped, and
+// existing budgets for the other categories are updated in place
+pub(crate) async fn budgets_from_averages(
     auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetsFromAveragesQuery>,
     axum::extract::State(state): axum::extract::State<AppState>,
-) -> Result<axum::Json<Vec<Transaction>>, (axum::http::StatusCode, String)> {
+) -> Result<(axum::http::StatusCode, axum::Json<Vec<Budget>>), (axum::http::StatusCode, String)> {
+
+    let months = query.months.unwrap_or(DEFAULT_AVERAGE_MONTHS).clamp(1, MAX_BUDGET_HISTORY_MONTHS);
+    let today = user_today(&state.pool, auth.user_id).await?;
+    let target_month = month_start_of(query.target_month.unwrap_or(today));
+    ensure_budget_month_editable(&state, auth.user_id, target_month).await?;
 
-    // fetch all the user's transactions from the database
-    let transactions = sqlx::query!(
-        "SELECT amount, kind, category, date, description FROM transactions WHERE user_id = $1",
-        auth.user_id
+    // write inside a database transaction, so it can be undone if it takes the month past the category cap
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    lock_budget_month(&mut tx, auth.user_id, target_month).await?;
+    let categories_before = count_budget_categories(&mut tx, auth.user_id, target_month).await?;
+
+    // spend counts the same way as budget progress; the average divides by every month in the window (quiet
+    // months count as zero) and is rounded to whole cents like any other budget
+    let rows = sqlx::query(
+        "WITH averages AS (
+            SELECT category, ROUND(SUM(amount) / $3, 2) AS amount
+            FROM transactions
+            WHERE user_id = $1
+            AND kind = 'expense'
+            AND category IS NOT NULL AND TRIM(category) <> ''
+            AND date >= ($2::date - make_interval(months => $3))::date
+            AND date < $2
+            AND (status = 'cleared' OR $4)
+            AND NOT exclude_from_budget
+            AND date <= $5
+            GROUP BY category
+            HAVING SUM(amount) > 0
+        )
+        INSERT INTO budgets (user_id, month, category, amount)
+        SELECT $1, $2, category, amount FROM averages
+        ON CONFLICT (user_id, month, category)
+        DO UPDATE SET amount = EXCLUDED.amount, updated_at = CURRENT_TIMESTAMP
+        RETURNING month, category, amount"
     )
-    .fetch_all(&state.pool)
+    .bind(auth.user_id)
+    .bind(target_month)
+    .bind(months as i32)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&mut *tx)
     .await
-    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
-    // map the transactions from the database into Transaction structs
-    let result: Vec<Transaction> = transactions
+    let categories_after = count_budget_categories(&mut tx, auth.user_id, target_month).await?;
+    ensure_within_budget_category_cap(&state.config, categories_before, categories_after)?;
+    tx.commit().await.map_err(db_error)?;
+
+    let mut result: Vec<Budget> = rows
         .into_iter()
-        .map(|transaction| Transaction {
+        .map(|row| Budget {
             user_id: auth.user_id,
-            amount: transaction.amount,
-            kind: match transaction.kind.as_str() {
-                "income" => TransactionKind::Income,
-                "expense" => TransactionKind::Expense,
-                _ => panic!("Invalid transaction kind in database"),
-            },
-            category: transaction.category,
-            date: transaction.date,
-            description: transaction.description,   
+            month: row.get("month"),
+            category: row.get("category"),
+            amount: row.get("amount"),
         })
         .collect();
+    result.sort_by(|a, b| a.category.cmp(&b.category));
 
-    Ok(axum::Json(result))
+    Ok((axum::http::StatusCode::CREATED, axum::Json(result)))
 }
 
-/* budgets */
+// route for suggesting a budget for one category, from its average monthly spend over the months before `month`
+// a category with no spend there gets the average monthly spend of the user's other categories instead
+pub(crate) async fn suggest_budget(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetSuggestionQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<BudgetSuggestion>, (axum::http::StatusCode, String)> {
+
+    let category = query.category.trim();
+    if category.is_empty() {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "category must not be empty".to_string()));
+    }
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+    let month = month_start_of(query.month.unwrap_or(today));
+
+    // spend is counted and averaged the same way as budgets generated from averages
+    let row = sqlx::query(
+        "WITH spend AS (
+            SELECT category, amount, date_trunc('month', date) AS month
+            FROM transactions
+            WHERE user_id = $1
+            AND kind = 'expense'
+            AND category IS NOT NULL AND TRIM(category) <> ''
+            AND date >= ($2::date - make_interval(months => $3))::date
+            AND date < $2
+            AND (status = 'cleared' OR $5)
+            AND NOT exclude_from_budget
+            AND date <= $6
+        )
+        SELECT
+            (SELECT ROUND(COALESCE(SUM(amount), 0) / $3, 2) FROM spend WHERE category = $4) AS category_average,
+            (SELECT COUNT(DISTINCT month) FROM spend WHERE category = $4) AS sample_months,
+            (
+                SELECT ROUND(COALESCE(SUM(amount) / NULLIF(COUNT(DISTINCT category), 0), 0) / $3, 2)
+                FROM spend WHERE category <> $4
+            ) AS default_average"
+    )
+    .bind(auth.user_id)
+    .bind(month)
+    .bind(DEFAULT_AVERAGE_MONTHS as i32)
+    .bind(category)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
 
-// route for creating/updating a budget (upsert)
-pub(crate) async fn upsert_budget(
+    let sample_months: i64 = row.get("sample_months");
+    let (suggested_amount, basis) = if sample_months > 0 {
+        (row.get("category_average"), SuggestionBasis::History)
+    } else {
+        (row.get("default_average"), SuggestionBasis::Default)
+    };
+
+    Ok(axum::Json(BudgetSuggestion {
+        category: category.to_string(),
+        month,
+        suggested_amount,
+        basis,
+        sample_months,
+    }))
+}
+
+// route for setting a month's overall spending limits, across all categories (leaving both out clears them)
+pub(crate) async fn upsert_monthly_limits(
     auth: AuthenticatedUser,
     axum::extract::State(state): axum::extract::State<AppState>,
-    axum::extract::Json(req): axum::extract::Json<UpsertBudgetRequest>
+    JsonBody(req): JsonBody<UpsertMonthlyLimitsRequest>
 ) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
 
-    // insert the budget into the database (or update if it already exists)
-    sqlx::query!(
-        "INSERT INTO budgets (user_id, month, category, amount)
-         VALUES ($1, $2, $3, $4)
-         ON CONFLICT (user_id, month, category)
-         DO UPDATE SET amount = EXCLUDED.amount, updated_at = CURRENT_TIMESTAMP",
-        auth.user_id,
-        req.month,
-        req.category,
-        req.amount
+    ensure_limits_valid(&req.limits)?;
+    ensure_budget_month_editable(&state, auth.user_id, req.month).await?;
+
+    sqlx::query(
+        "INSERT INTO monthly_limits (user_id, month, warn_at, cap_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, month)
+        DO UPDATE SET warn_at = EXCLUDED.warn_at, cap_at = EXCLUDED.cap_at, updated_at = CURRENT_TIMESTAMP"
     )
+    .bind(auth.user_id)
+    .bind(month_start_of(req.month))
+    .bind(req.limits.warn_at)
+    .bind(req.limits.cap_at)
     .execute(&state.pool)
     .await
-    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
     Ok(axum::http::StatusCode::CREATED)
 }
 
+// route for a month's overall spend against its overall limits (the current month by default)
+// spend counts the same way as budget progress
+pub(crate) async fn get_monthly_limit_status(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<MonthlyLimitStatus>, (axum::http::StatusCode, String)> {
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+    let month = month_start_of(query.month.unwrap_or(today));
+
+    let row = sqlx::query(
+        "SELECT
+            m.warn_at,
+            m.cap_at,
+            (
+                SELECT COALESCE(SUM(t.amount), 0)
+                FROM transactions t
+                WHERE t.user_id = $1
+                AND t.kind = 'expense'
+                AND t.date >= $2 AND t.date < $3
+                AND (t.status = 'cleared' OR $4)
+                AND NOT t.exclude_from_budget
+                AND t.date <= $5
+            )::numeric AS spent
+        FROM (SELECT 1) one
+        LEFT JOIN monthly_limits m ON m.user_id = $1 AND m.month = $2"
+    )
+    .bind(auth.user_id)
+    .bind(month)
+    .bind(next_month_start_of(month))
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let spent: Decimal = row.get("spent");
+    let limits = BudgetLimits { warn_at: row.get("warn_at"), cap_at: row.get("cap_at") };
+    let (warn_crossed, cap_exceeded) = limits_crossed(&limits, spent);
+
+    Ok(axum::Json(MonthlyLimitStatus { month, spent, limits, warn_crossed, cap_exceeded }))
+}
+
 
 // route for getting budgets for authenticated user (optionally filtered by month)
 pub(crate) async fn get_budgets(
@@ -225,7 +2133,7 @@ pub(crate) async fn get_budgets(
         )
         .fetch_all(&state.pool)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(db_error)?;
 
         rows.into_iter()
             .map(|row| Budget {
@@ -246,7 +2154,7 @@ pub(crate) async fn get_budgets(
         )
         .fetch_all(&state.pool)
         .await
-        .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(db_error)?;
 
         rows.into_iter()
             .map(|row| Budget {
@@ -266,63 +2174,92 @@ pub(crate) async fn get_budgets(
 // route for getting budget progress for authenticated user (budget vs spent) for a month
 pub(crate) async fn get_budget_progress(
     auth: AuthenticatedUser,
-    axum::extract::Query(query): axum::extract::Query<BudgetQuery>,
+    axum::extract::Query(query): axum::extract::Query<BudgetProgressQuery>,
     axum::extract::State(state): axum::extract::State<AppState>,
 ) -> Result<axum::Json<Vec<BudgetProgress>>, (axum::http::StatusCode, String)> {
 
-    use chrono::Datelike;
-
-    // default to current month if not provided
-    let month_start = if let Some(m) = query.month {
-        m
-    } else {
-        let today = chrono::Utc::now().date_naive();
-        chrono::NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap()
-    };
+    // default to the current month (in the user's timezone) if not provided
+    let today = user_today(&state.pool, auth.user_id).await?;
+    let month_start = query.month.unwrap_or(month_start_of(today));
 
     // compute next month start (exclusive end bound)
-    let (ny, nm) = if month_start.month() == 12 {
-        (month_start.year() + 1, 1)
-    } else {
-        (month_start.year(), month_start.month() + 1)
-    };
-    let next_month_start = chrono::NaiveDate::from_ymd_opt(ny, nm, 1).unwrap();
+    let next_month_start = next_month_start_of(month_start);
+
+    // escape LIKE wildcards so the filter is a plain prefix match
+    let category_prefix = query.category.map(|category| {
+        category.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    });
+
+    // a household's budgets are spent against by every member, a personal budget just by the user
+    let user_ids = scope_user_ids(&state.pool, auth.user_id, query.household_id).await?;
 
     // join budgets with transactions to compute "spent" per category (expenses only)
-    let rows = sqlx::query!(
-        "SELECT
-            b.category as \"category!\",
-            b.amount as \"budget_amount!\",
-            COALESCE(SUM(t.amount), 0)::numeric as \"spent!\"
-        FROM budgets b
+    // pending transactions only count when the instance is configured to include them, and future-dated ones not until their date
+    let rows = sqlx::query(
+        "WITH scoped_budgets AS (
+            SELECT category, amount, warn_at, cap_at
+            FROM budgets
+            WHERE $7::uuid IS NULL AND user_id = $1 AND month = $2
+            UNION ALL
+            SELECT category, amount, NULL::numeric, NULL::numeric
+            FROM household_budgets
+            WHERE household_id = $7 AND month = $2
+        )
+        SELECT
+            b.category,
+            b.amount AS budget_amount,
+            b.warn_at,
+            b.cap_at,
+            COALESCE(SUM(t.amount), 0)::numeric AS spent
+        FROM scoped_budgets b
         LEFT JOIN transactions t
-        ON t.user_id = b.user_id
+        ON t.user_id = ANY($8)
         AND t.kind = 'expense'
         AND t.category = b.category
         AND t.date >= $2
         AND t.date < $3
-        WHERE b.user_id = $1
-        AND b.month = $2
-        GROUP BY b.category, b.amount
-        ORDER BY b.category ASC",
-        auth.user_id,
-        month_start,
-        next_month_start
+        AND (t.status = 'cleared' OR $4)
+        AND NOT t.exclude_from_budget
+        AND t.date <= $6
+        WHERE ($5::text IS NULL OR b.category ILIKE ($5 || '%'))
+        GROUP BY b.category, b.amount, b.warn_at, b.cap_at
+        ORDER BY b.category ASC"
     )
+    .bind(auth.user_id)
+    .bind(month_start)
+    .bind(next_month_start)
+    .bind(state.config.count_pending_transactions)
+    .bind(category_prefix)
+    .bind(today)
+    .bind(query.household_id)
+    .bind(&user_ids)
     .fetch_all(&state.pool)
     .await
-    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
 
+    // spending is too fast when the share of the budget spent is ahead of the share of the month gone by,
+    // i.e. spent / budget > days_elapsed / days_in_month (cross-multiplied, so a zero budget doesn't divide by zero)
+    let (days_elapsed, days_in_month) = month_days_elapsed(month_start_of(month_start), today);
+
     let result: Vec<BudgetProgress> = rows
         .into_iter()
         .map(|row| {
-            let remaining = row.budget_amount - row.spent;
+            let budget_amount: Decimal = row.get("budget_amount");
+            let spent: Decimal = row.get("spent");
+            let remaining = budget_amount - spent;
+            let pace_warning = spent * Decimal::from(days_in_month) > budget_amount * Decimal::from(days_elapsed);
+            let limits = BudgetLimits { warn_at: row.get("warn_at"), cap_at: row.get("cap_at") };
+            let (warn_crossed, cap_exceeded) = limits_crossed(&limits, spent);
             BudgetProgress {
-                category: row.category,
-                budget_amount: row.budget_amount,
-                spent: row.spent,
+                category: row.get("category"),
+                budget_amount,
+                spent,
                 remaining,
+                pace_warning,
+                limits,
+                warn_crossed,
+                cap_exceeded,
             }
         })
         .collect();
@@ -330,13 +2267,137 @@ pub(crate) async fn get_budget_progress(
     Ok(axum::Json(result))
 }
 
+// route for just the overspent budgets in a month (the current month by default), the biggest overage first
+// spend counts the same way as budget progress, which this filters
+pub(crate) async fn get_budget_overruns(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<BudgetOverrun>>, (axum::http::StatusCode, String)> {
+
+    let axum::Json(progress) = get_budget_progress(
+        auth,
+        axum::extract::Query(BudgetProgressQuery { month: query.month.map(month_start_of), category: None, household_id: None }),
+        axum::extract::State(state),
+    )
+    .await?;
+
+    let mut result: Vec<BudgetOverrun> = progress
+        .into_iter()
+        .filter(|progress| progress.spent > progress.budget_amount)
+        .map(|progress| BudgetOverrun {
+            category: progress.category,
+            budget: progress.budget_amount,
+            spent: progress.spent,
+            overage: progress.spent - progress.budget_amount,
+        })
+        .collect();
+    result.sort_by(|a, b| b.overage.cmp(&a.overage).then_with(|| a.category.cmp(&b.category)));
+
+    Ok(axum::Json(result))
+}
+
+// route for a month's overall budget adherence (the current month by default): its budgets and their spend summed into
+// one headline figure, so only budgeted categories count towards spent
+pub(crate) async fn get_budget_adherence(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<BudgetAdherence>, (axum::http::StatusCode, String)> {
+
+    let month = match query.month {
+        Some(month) => month_start_of(month),
+        None => month_start_of(user_today(&state.pool, auth.user_id).await?),
+    };
+
+    let axum::Json(progress) = get_budget_progress(
+        auth,
+        axum::extract::Query(BudgetProgressQuery { month: Some(month), category: None, household_id: None }),
+        axum::extract::State(state),
+    )
+    .await?;
+
+    let budgeted: Decimal = progress.iter().map(|progress| progress.budget_amount).sum();
+    let spent: Decimal = progress.iter().map(|progress| progress.spent).sum();
+
+    let percent_spent = (!budgeted.is_zero()).then(|| (spent * Decimal::from(100) / budgeted).round_dp(1));
+    let status = match percent_spent {
+        None => AdherenceStatus::NoBudget,
+        Some(_) if spent > budgeted => AdherenceStatus::Over,
+        Some(percent) if percent >= Decimal::from(ADHERENCE_ON_TRACK_PERCENT) => AdherenceStatus::OnTrack,
+        Some(_) => AdherenceStatus::Under,
+    };
+
+    Ok(axum::Json(BudgetAdherence { month, budgeted, spent, percent_spent, status }))
+}
+
+// route for one category's budget vs spend over each of the last N months (including the current one), oldest first
+// months without a budget are still listed, so a chart has no gaps
+pub(crate) async fn get_budget_progress_history(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetHistoryQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<BudgetHistoryPoint>>, (axum::http::StatusCode, String)> {
+
+    let months = query.months.unwrap_or(6).clamp(1, MAX_BUDGET_HISTORY_MONTHS);
+    let today = user_today(&state.pool, auth.user_id).await?;
+    let current_month = month_start_of(today);
+
+    // spend is worked out the same way as budget progress, once per month of a generated series
+    let rows = sqlx::query(
+        "WITH months AS (
+            SELECT generate_series($3::date - make_interval(months => $4 - 1), $3::date, interval '1 month')::date AS month
+        )
+        SELECT
+            m.month,
+            b.amount AS budgeted,
+            COALESCE(SUM(t.amount), 0)::numeric AS spent
+        FROM months m
+        LEFT JOIN budgets b
+        ON b.user_id = $1
+        AND b.month = m.month
+        AND b.category = $2
+        LEFT JOIN transactions t
+        ON t.user_id = $1
+        AND t.kind = 'expense'
+        AND t.category = $2
+        AND t.date >= m.month
+        AND t.date < (m.month + interval '1 month')::date
+        AND (t.status = 'cleared' OR $5)
+        AND NOT t.exclude_from_budget
+        AND t.date <= $6
+        GROUP BY m.month, b.amount
+        ORDER BY m.month ASC"
+    )
+    .bind(auth.user_id)
+    .bind(query.category.trim())
+    .bind(current_month)
+    .bind(months as i32)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<BudgetHistoryPoint> = rows
+        .into_iter()
+        .map(|row| BudgetHistoryPoint {
+            month: row.get("month"),
+            budgeted: row.get("budgeted"),
+            spent: row.get("spent"),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
 
 
 // route for semantically searching transactions by embedding similarity
 pub(crate) async fn semantic_transaction_search(
     auth: AuthenticatedUser,
     axum::extract::State(state): axum::extract::State<AppState>,
-    axum::extract::Json(req): axum::extract::Json<SemanticSearchRequest>,
+    JsonBody(req): JsonBody<SemanticSearchRequest>,
 ) -> Result<axum::Json<Vec<Transaction>>, (axum::http::StatusCode, String)> {
 
     // convert the search query into an embedding
@@ -359,7 +2420,7 @@ pub(crate) async fn semantic_transaction_search(
      // this specifically calculates cosine distance, which is 1 - cosine similarity, so smaller values are more similar
      // therefore we order by this value ascending to get the most similar results first
     let rows = sqlx::query(
-        "SELECT t.user_id, t.amount, t.kind, t.category, t.date, t.description
+        "SELECT t.id, t.user_id, t.amount, t.kind, t.category, t.date, t.description, t.source, t.status, t.exclude_from_budget, t.merchant, t.location, t.seq, t.updated_at
         FROM transaction_embeddings embed
         JOIN transactions t ON t.id = embed.transaction_id
         WHERE embed.user_id = $1
@@ -371,33 +2432,352 @@ pub(crate) async fn semantic_transaction_search(
     .bind(amount as i64)
     .fetch_all(&state.pool)
     .await
-    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
     let transactions: Vec<Transaction> = rows
+        .iter()
+        .map(|row| transaction_from_row(row, auth.user_id, &state.config))
+        .collect();
+
+    Ok(axum::Json(transactions))
+}
+
+
+/* closed months */
+
+// route for closing a month, locking its transactions against changes (closing twice is a no-op)
+pub(crate) async fn close_month(
+    auth: AuthenticatedUser,
+    axum::extract::Path(month): axum::extract::Path<chrono::NaiveDate>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    sqlx::query("INSERT INTO closed_months (user_id, month) VALUES ($1, $2) ON CONFLICT (user_id, month) DO NOTHING")
+        .bind(auth.user_id)
+        .bind(month_start_of(month))
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// route for reopening a closed month so its transactions can be changed again
+pub(crate) async fn reopen_month(
+    auth: AuthenticatedUser,
+    axum::extract::Path(month): axum::extract::Path<chrono::NaiveDate>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    let result = sqlx::query("DELETE FROM closed_months WHERE user_id = $1 AND month = $2")
+        .bind(auth.user_id)
+        .bind(month_start_of(month))
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((axum::http::StatusCode::NOT_FOUND, "Month is not closed".to_string()));
+    }
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+/* categories */
+
+// route for getting the user's categories ordered by most recent (or most frequent) use, for a smart category picker
+pub(crate) async fn get_recent_categories(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<RecentCategoriesQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<CategoryUsage>>, (axum::http::StatusCode, String)> {
+
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    // pick between two fixed queries rather than building the ORDER BY from user input
+    let sql = match query.order.unwrap_or(CategoryOrder::Recent) {
+        CategoryOrder::Recent => {
+            "SELECT category, MAX(date) AS last_used, COUNT(*) AS uses
+            FROM transactions
+            WHERE user_id = $1 AND category IS NOT NULL AND category <> ''
+            GROUP BY category
+            ORDER BY MAX(date) DESC, MAX(created_at) DESC, category ASC
+            LIMIT $2"
+        }
+        CategoryOrder::Frequent => {
+            "SELECT category, MAX(date) AS last_used, COUNT(*) AS uses
+            FROM transactions
+            WHERE user_id = $1 AND category IS NOT NULL AND category <> ''
+            GROUP BY category
+            ORDER BY COUNT(*) DESC, MAX(date) DESC, category ASC
+            LIMIT $2"
+        }
+    };
+
+    let rows = sqlx::query(sql)
+        .bind(auth.user_id)
+        .bind(limit)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let result: Vec<CategoryUsage> = rows
         .into_iter()
-        .map(|row| {
-            let kind_str: String = row.get("kind");
-            let kind = match kind_str.as_str() {
-                "income" => TransactionKind::Income,
-                "expense" => TransactionKind::Expense,
-                _ => TransactionKind::Expense,
-            };
-
-            Transaction {
-                user_id: row.get("user_id"),
-                amount: row.get("amount"),
-                kind,
-                category: row.get("category"),
-                date: row.get("date"),
+        .map(|row| CategoryUsage {
+            category: row.get("category"),
+            last_used: row.get("last_used"),
+            uses: row.get("uses"),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+// route for the user's past descriptions starting with a prefix, most used first, for autocompleting descriptions
+pub(crate) async fn get_description_suggestions(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<DescriptionSuggestionsQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<DescriptionUsage>>, (axum::http::StatusCode, String)> {
+
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    // with no encryption secret every description is plaintext, so postgres can do the matching, counting and capping
+    if state.config.notes_encryption_secret.is_none() {
+        // escape LIKE wildcards so the filter is a plain prefix match
+        let prefix = query.prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+
+        let rows = sqlx::query(
+            "SELECT description, COUNT(*) AS uses, MAX(date) AS last_used
+            FROM transactions
+            WHERE user_id = $1 AND TRIM(description) <> '' AND description ILIKE ($2 || '%')
+            GROUP BY description
+            ORDER BY COUNT(*) DESC, MAX(date) DESC, description ASC
+            LIMIT $3"
+        )
+        .bind(auth.user_id)
+        .bind(prefix)
+        .bind(limit)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+        let result: Vec<DescriptionUsage> = rows
+            .into_iter()
+            .map(|row| DescriptionUsage {
                 description: row.get("description"),
-            }
+                uses: row.get("uses"),
+                last_used: row.get("last_used"),
+            })
+            .collect();
+
+        return Ok(axum::Json(result));
+    }
+
+    // otherwise descriptions may be stored encrypted (even with ENCRYPT_NOTES since turned off), so the matching is
+    // done here after decrypting, and each copy of a description has its own nonce so they're merged by their text
+    let prefix = query.prefix.to_lowercase();
+    let rows = sqlx::query(
+        "SELECT description, COUNT(*) AS uses, MAX(date) AS last_used
+        FROM transactions
+        WHERE user_id = $1 AND description IS NOT NULL AND TRIM(description) <> ''
+        GROUP BY description"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let mut merged: std::collections::HashMap<String, DescriptionUsage> = std::collections::HashMap::new();
+    for row in rows {
+        let description = open_note(&state.config, auth.user_id, row.get("description")).unwrap_or_default();
+        if description.trim().is_empty() || !description.to_lowercase().starts_with(&prefix) {
+            continue;
+        }
+        let uses: i64 = row.get("uses");
+        let last_used: chrono::NaiveDate = row.get("last_used");
+
+        let entry = merged.entry(description.clone()).or_insert(DescriptionUsage { description, uses: 0, last_used });
+        entry.uses += uses;
+        entry.last_used = entry.last_used.max(last_used);
+    }
+
+    let mut result: Vec<DescriptionUsage> = merged.into_values().collect();
+    result.sort_by(|a, b| {
+        b.uses.cmp(&a.uses)
+            .then_with(|| b.last_used.cmp(&a.last_used))
+            .then_with(|| a.description.cmp(&b.description))
+    });
+    result.truncate(limit as usize);
+
+    Ok(axum::Json(result))
+}
+
+// route for listing every category the user has (from transactions or the month's budgets) with that month's
+// budget and spend, for a category management screen (month defaults to the current one)
+pub(crate) async fn get_category_overview(
+    auth: AuthenticatedUser,
+    axum::extract::Query(query): axum::extract::Query<BudgetQuery>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<CategoryOverview>>, (axum::http::StatusCode, String)> {
+
+    let today = user_today(&state.pool, auth.user_id).await?;
+    let month_start = month_start_of(query.month.unwrap_or(today));
+    let next_month_start = next_month_start_of(month_start);
+
+    // spend counts the same way as budget progress, so the two screens agree
+    let rows = sqlx::query(
+        "WITH categories AS (
+            SELECT category FROM transactions
+            WHERE user_id = $1 AND category IS NOT NULL AND TRIM(category) <> ''
+            UNION
+            SELECT category FROM budgets
+            WHERE user_id = $1 AND month = $2
+        )
+        SELECT
+            c.category,
+            b.amount AS budget,
+            COALESCE(SUM(t.amount), 0)::numeric AS spent
+        FROM categories c
+        LEFT JOIN budgets b
+        ON b.user_id = $1
+        AND b.month = $2
+        AND b.category = c.category
+        LEFT JOIN transactions t
+        ON t.user_id = $1
+        AND t.kind = 'expense'
+        AND t.category = c.category
+        AND t.date >= $2
+        AND t.date < $3
+        AND (t.status = 'cleared' OR $4)
+        AND NOT t.exclude_from_budget
+        AND t.date <= $5
+        GROUP BY c.category, b.amount
+        ORDER BY c.category ASC"
+    )
+    .bind(auth.user_id)
+    .bind(month_start)
+    .bind(next_month_start)
+    .bind(state.config.count_pending_transactions)
+    .bind(today)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<CategoryOverview> = rows
+        .into_iter()
+        .map(|row| CategoryOverview {
+            category: row.get("category"),
+            budget: row.get("budget"),
+            spent: row.get("spent"),
         })
         .collect();
 
-    Ok(axum::Json(transactions))
+    Ok(axum::Json(result))
+}
+
+// route for adding a category rule (matching descriptions get the rule's category when added, or via apply-rules)
+pub(crate) async fn create_category_rule(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<CreateCategoryRuleRequest>
+) -> Result<(axum::http::StatusCode, axum::Json<CategoryRule>), (axum::http::StatusCode, String)> {
+
+    let match_contains = req.match_contains.trim();
+    let category = req.category.trim();
+
+    // an empty match would match every description
+    if match_contains.is_empty() || category.is_empty() {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "match_contains and category must not be empty".to_string()));
+    }
+    ensure_max_length("match_contains", match_contains, MAX_RULE_MATCH_LENGTH)?;
+    ensure_max_length("category", category, MAX_CATEGORY_LENGTH)?;
+
+    let id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO category_rules (user_id, match_contains, category, priority) VALUES ($1, $2, $3, $4) RETURNING id"
+    )
+    .bind(auth.user_id)
+    .bind(match_contains)
+    .bind(category)
+    .bind(req.priority)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok((
+        axum::http::StatusCode::CREATED,
+        axum::Json(CategoryRule {
+            id,
+            match_contains: match_contains.to_string(),
+            category: category.to_string(),
+            priority: req.priority,
+        }),
+    ))
+}
+
+// route for listing the user's category rules in the order they're evaluated
+pub(crate) async fn get_category_rules(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<CategoryRule>>, (axum::http::StatusCode, String)> {
+
+    let rows = sqlx::query(
+        "SELECT id, match_contains, category, priority
+        FROM category_rules
+        WHERE user_id = $1
+        ORDER BY priority ASC, created_at ASC"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<CategoryRule> = rows
+        .into_iter()
+        .map(|row| CategoryRule {
+            id: row.get("id"),
+            match_contains: row.get("match_contains"),
+            category: row.get("category"),
+            priority: row.get("priority"),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+// route for deleting one of the user's category rules (already categorized transactions keep their category)
+pub(crate) async fn delete_category_rule(
+    auth: AuthenticatedUser,
+    axum::extract::Path(rule_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    let result = sqlx::query("DELETE FROM category_rules WHERE id = $1 AND user_id = $2")
+        .bind(rule_id)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((axum::http::StatusCode::NOT_FOUND, "Rule not found".to_string()));
+    }
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
 }
 
 
+/* landing */
+
+// route for the root path when the frontend isn't being served, pointing callers at the API
+pub(crate) async fn root_handler() -> axum::Json<ApiInfo> {
+    axum::Json(ApiInfo {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        api: "/api",
+    })
+}
+
 /* testing */
 
 // test route
@@ -0,0 +1,279 @@
+use sqlx::Row;
+
+use crate::extract::JsonBody;
+use crate::handlers::{db_error, ensure_amount_fits, ensure_max_length, month_start_of};
+use crate::models::*;
+
+/* households */
+
+// helper function to get the users whose transactions a request covers: just the caller, or every member of a
+// household the caller belongs to (404 for a household they aren't in, so its existence isn't given away)
+pub(crate) async fn scope_user_ids(
+    pool: &sqlx::PgPool,
+    user_id: uuid::Uuid,
+    household_id: Option<uuid::Uuid>,
+) -> Result<Vec<uuid::Uuid>, (axum::http::StatusCode, String)> {
+
+    let Some(household_id) = household_id else {
+        return Ok(vec![user_id]);
+    };
+
+    ensure_household_member(pool, user_id, household_id).await?;
+
+    sqlx::query_scalar("SELECT user_id FROM household_members WHERE household_id = $1")
+        .bind(household_id)
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)
+}
+
+// helper function to check the user belongs to a household
+async fn ensure_household_member(
+    pool: &sqlx::PgPool,
+    user_id: uuid::Uuid,
+    household_id: uuid::Uuid,
+) -> Result<(), (axum::http::StatusCode, String)> {
+
+    let is_member: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM household_members WHERE household_id = $1 AND user_id = $2)"
+    )
+    .bind(household_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error)?;
+
+    if !is_member {
+        return Err((axum::http::StatusCode::NOT_FOUND, "Household not found".to_string()));
+    }
+
+    Ok(())
+}
+
+// route for creating a household, with the creator as its first member
+pub(crate) async fn create_household(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<CreateHouseholdRequest>
+) -> Result<(axum::http::StatusCode, axum::Json<Household>), (axum::http::StatusCode, String)> {
+
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "name must not be empty".to_string()));
+    }
+    ensure_max_length("name", name, MAX_HOUSEHOLD_NAME_LENGTH)?;
+
+    // the household and its first membership go in together
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+
+    let row = sqlx::query("INSERT INTO households (name) VALUES ($1) RETURNING id, name, created_at")
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    let household = Household {
+        id: row.get("id"),
+        name: row.get("name"),
+        created_at: row.get("created_at"),
+    };
+
+    sqlx::query("INSERT INTO household_members (household_id, user_id) VALUES ($1, $2)")
+        .bind(household.id)
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok((axum::http::StatusCode::CREATED, axum::Json(household)))
+}
+
+// route for listing the households the user belongs to
+pub(crate) async fn get_households(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<Household>>, (axum::http::StatusCode, String)> {
+
+    let rows = sqlx::query(
+        "SELECT h.id, h.name, h.created_at
+        FROM households h
+        JOIN household_members m ON m.household_id = h.id
+        WHERE m.user_id = $1
+        ORDER BY h.created_at ASC"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<Household> = rows
+        .into_iter()
+        .map(|row| Household {
+            id: row.get("id"),
+            name: row.get("name"),
+            created_at: row.get("created_at"),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+// route for inviting an existing user to a household (any member can invite others)
+// the user only becomes a member, and their transactions part of household queries, once they accept; inviting
+// someone who's already a member or already invited is a no-op
+pub(crate) async fn add_household_member(
+    auth: AuthenticatedUser,
+    axum::extract::Path(household_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<AddHouseholdMemberRequest>
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    ensure_household_member(&state.pool, auth.user_id, household_id).await?;
+
+    let invitee_id: uuid::Uuid = sqlx::query_scalar("SELECT id FROM users WHERE username = $1")
+        .bind(req.username.trim())
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((axum::http::StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    sqlx::query(
+        "INSERT INTO household_invites (household_id, user_id, invited_by)
+        SELECT $1, $2, $3
+        WHERE NOT EXISTS (SELECT 1 FROM household_members WHERE household_id = $1 AND user_id = $2)
+        ON CONFLICT (household_id, user_id) DO NOTHING"
+    )
+    .bind(household_id)
+    .bind(invitee_id)
+    .bind(auth.user_id)
+    .execute(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(axum::http::StatusCode::CREATED)
+}
+
+// route for listing the household invites waiting on the user, oldest first
+pub(crate) async fn get_household_invites(
+    auth: AuthenticatedUser,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::Json<Vec<HouseholdInvite>>, (axum::http::StatusCode, String)> {
+
+    let rows = sqlx::query(
+        "SELECT i.household_id, h.name AS household_name, u.username AS invited_by, i.created_at
+        FROM household_invites i
+        JOIN households h ON h.id = i.household_id
+        JOIN users u ON u.id = i.invited_by
+        WHERE i.user_id = $1
+        ORDER BY i.created_at ASC"
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let result: Vec<HouseholdInvite> = rows
+        .into_iter()
+        .map(|row| HouseholdInvite {
+            household_id: row.get("household_id"),
+            household_name: row.get("household_name"),
+            invited_by: row.get("invited_by"),
+            created_at: row.get("created_at"),
+        })
+        .collect();
+
+    Ok(axum::Json(result))
+}
+
+// route for accepting an invite, which makes the user a member of the household (404 without an invite)
+pub(crate) async fn accept_household_invite(
+    auth: AuthenticatedUser,
+    axum::extract::Path(household_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    // the invite is used up and the membership added together
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+
+    let result = sqlx::query("DELETE FROM household_invites WHERE household_id = $1 AND user_id = $2")
+        .bind(household_id)
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((axum::http::StatusCode::NOT_FOUND, "Invite not found".to_string()));
+    }
+
+    sqlx::query(
+        "INSERT INTO household_members (household_id, user_id) VALUES ($1, $2)
+        ON CONFLICT (household_id, user_id) DO NOTHING"
+    )
+    .bind(household_id)
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// route for declining an invite (404 without an invite)
+pub(crate) async fn decline_household_invite(
+    auth: AuthenticatedUser,
+    axum::extract::Path(household_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    let result = sqlx::query("DELETE FROM household_invites WHERE household_id = $1 AND user_id = $2")
+        .bind(household_id)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err((axum::http::StatusCode::NOT_FOUND, "Invite not found".to_string()));
+    }
+
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// route for creating or updating one of a household's shared budgets
+pub(crate) async fn upsert_household_budget(
+    auth: AuthenticatedUser,
+    axum::extract::Path(household_id): axum::extract::Path<uuid::Uuid>,
+    axum::extract::State(state): axum::extract::State<AppState>,
+    JsonBody(req): JsonBody<UpsertHouseholdBudgetRequest>
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, String)> {
+
+    ensure_household_member(&state.pool, auth.user_id, household_id).await?;
+
+    // same rules as personal budgets: whole cents and a non-blank category
+    ensure_amount_fits(req.amount, DEFAULT_AMOUNT_SCALE)?;
+    let category = req.category.trim();
+    if category.is_empty() {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "category must not be empty".to_string()));
+    }
+    ensure_max_length("category", category, MAX_CATEGORY_LENGTH)?;
+
+    sqlx::query(
+        "INSERT INTO household_budgets (household_id, month, category, amount)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (household_id, month, category)
+        DO UPDATE SET amount = EXCLUDED.amount, updated_at = CURRENT_TIMESTAMP"
+    )
+    .bind(household_id)
+    .bind(month_start_of(req.month))
+    .bind(category)
+    .bind(req.amount)
+    .execute(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(axum::http::StatusCode::CREATED)
+}
@@ -0,0 +1,54 @@
+use crate::config::Config;
+
+/* background jobs */
+
+// moves transactions dated more than `years` years ago into the archived_transactions table
+// and returns how many were moved. this is safe to run repeatedly, since a moved row is no longer in
+// transactions to be picked up again; if an id is somehow already archived the whole statement fails
+// (and moves nothing) rather than deleting a row it couldn't archive
+pub async fn archive_old_transactions(pool: &sqlx::PgPool, years: u32) -> Result<u64, sqlx::Error> {
+    let today = chrono::Utc::now().date_naive();
+    let cutoff = today
+        .checked_sub_months(chrono::Months::new(years * 12))
+        .unwrap_or(chrono::NaiveDate::MIN);
+
+    // delete from the hot table and insert into the archive in one statement, so a crash can't
+    // leave a transaction in both tables (or neither)
+    let result = sqlx::query(
+        "WITH moved AS (
+            DELETE FROM transactions
+            WHERE date < $1
+            RETURNING id, user_id, amount, kind, category, description, source, status, exclude_from_budget, merchant, location, seq, date, created_at, updated_at
+        )
+        INSERT INTO archived_transactions (id, user_id, amount, kind, category, description, source, status, exclude_from_budget, merchant, location, seq, date, created_at, updated_at)
+        SELECT id, user_id, amount, kind, category, description, source, status, exclude_from_budget, merchant, location, seq, date, created_at, updated_at FROM moved"
+    )
+    .bind(cutoff)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// starts the archival job in the background if ARCHIVE_AFTER_YEARS is configured
+pub fn spawn_archival_job(pool: sqlx::PgPool, config: &Config) {
+    let Some(years) = config.archive_after_years else {
+        return;
+    };
+
+    // never tick faster than once an hour (an interval of zero would panic)
+    let interval = std::time::Duration::from_secs(config.archive_interval_hours.max(1) * 3600);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match archive_old_transactions(&pool, years).await {
+                Ok(moved) => tracing::info!("Archived {} transactions older than {} years", moved, years),
+                Err(e) => tracing::error!("Transaction archival failed: {}", e),
+            }
+        }
+    });
+}
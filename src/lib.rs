@@ -1,15 +1,30 @@
 // this lib.rs makes the binary's public items available for integration tests
 // by exposing modules and re-exporting public items
 
+pub mod analytics;
 pub mod app;
 pub mod auth;
+pub mod cache;
+pub mod config;
 pub mod embeddings;
+pub mod export;
+pub mod extract;
 pub mod handlers;
+pub mod households;
+pub mod jobs;
 pub mod models;
+pub mod notes;
+pub mod rate_limit;
+pub mod redact;
+pub mod totp;
 
-pub use app::build_app;
-pub use auth::verify_jwt;
+pub use app::{api_router, build_app, frontend_router};
+pub use auth::{select_jwt_secret, verify_jwt};
+pub use cache::AnalyticsCache;
+pub use config::{Config, Features};
 pub use models::AppState;
+pub use rate_limit::RateLimiter;
 pub use embeddings::{generate_transaction_embedding, store_transaction_embedding};
-pub use models::Transaction;
+pub use models::{Transaction, VerifiedToken};
+pub use jobs::archive_old_transactions;
 
@@ -1,13 +1,19 @@
 use sqlx::postgres::PgPoolOptions;
 
 // import from our library crate
-use financetracker::{AppState, build_app};
+use financetracker::{AnalyticsCache, AppState, Config, RateLimiter, build_app};
+use financetracker::config::{MAX_DB_CONNECT_BACKOFF_SECS, MAX_JWT_EXPIRATION_HOURS};
+use financetracker::handlers::is_connection_error;
+use financetracker::redact::redact_url;
  
 #[tokio::main]
 async fn main() {
     // load in env file
     dotenvy::dotenv_override().ok();
 
+    // send tracing output (info and up) to stdout
+    tracing_subscriber::fmt::init();
+
     // set up the database connection
     let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 
@@ -20,31 +26,42 @@ async fn main() {
     // set up a reusable HTTP client for outbound API calls (like to OpenAI)
     let http_client = reqwest::Client::new();
 
-    // debugging
-    // print only host:port/path/query (everything after the last '@')
-    if let Some(i) = db_url.rfind('@') {
-        println!("DB target: {}", &db_url[i + 1..]);
-    } else {
-        println!("DB target: {}", db_url);
+    // read the optional runtime configuration
+    let config = Config::from_env();
+
+    // a huge token lifetime is almost certainly a typo, so it's clamped rather than honored
+    if config.jwt_expiration_hours > MAX_JWT_EXPIRATION_HOURS {
+        tracing::warn!(
+            "JWT_EXPIRATION_HOURS={} exceeds the maximum, using {} hours",
+            config.jwt_expiration_hours, MAX_JWT_EXPIRATION_HOURS
+        );
     }
 
+    // turning on encryption without a key would silently keep storing plaintext, so refuse to start instead
+    if config.encrypt_notes && config.notes_encryption_secret.is_none() {
+        panic!("ENCRYPT_NOTES is set but NOTES_ENCRYPTION_SECRET is not");
+    }
+
+    // log where we're connecting, never with the credentials
+    tracing::info!("DB target: {}", redact_url(&db_url));
+
 
     // create the connection pool, and connect lazily
     let pool = PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(config.db_max_connections)
         .connect_lazy(&db_url)
         .expect("Could not create database connection pool");
 
-    // run migrations when the environmental variable RUN_MIGRATIONS is set
-    if std::env::var("RUN_MIGRATIONS").is_ok() {
+    // run migrations when the environmental variable RUN_MIGRATIONS is "true" or "1" (RUN_MIGRATIONS=false leaves them alone)
+    if financetracker::config::env_flag("RUN_MIGRATIONS") {
         println!("Running database migrations...");
-        sqlx::migrate!("./backend/migrations")
-            .run(&pool)
-            .await
-            .expect("Could not run database migrations");
+        run_migrations_with_retry(&pool, &config).await;
         println!("Database migrations complete.");
     }
 
+    // start the transaction archival job (only runs if ARCHIVE_AFTER_YEARS is set)
+    financetracker::jobs::spawn_archival_job(pool.clone(), &config);
+
     // now, we set up the HTTP server so the frontend can call routes
     // we get the port number from the environment variable PORT, defaulting to 3000
     let port: u16 = std::env::var("PORT")
@@ -59,13 +76,49 @@ async fn main() {
 
 
     // set up the shared state
-    let state = AppState { pool, jwt_secret, openai_api_key, http_client };
+    let registration_limiter = RateLimiter::for_registration(&config);
+    let analytics_cache = AnalyticsCache::for_analytics(&config);
+    let state = AppState { pool, jwt_secret, openai_api_key, http_client, config, registration_limiter, analytics_cache };
 
     // set up the router with the state
     let app = build_app(state);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app.into_make_service()).await.unwrap();
+    // serve with connect info so handlers can see the client's address (used for rate limiting)
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap();
 
     println!("Hello, world!");
 }
+
+// run the migrations, retrying connection errors with exponential backoff since the database may still be starting up
+// (e.g. when it's deployed alongside the app), and exiting with a clear message once the retries run out. anything else
+// (a broken or missing migration, say) won't fix itself by waiting, so it exits straight away
+async fn run_migrations_with_retry(pool: &sqlx::PgPool, config: &Config) {
+    let max_backoff = std::time::Duration::from_secs(MAX_DB_CONNECT_BACKOFF_SECS);
+    let mut backoff = std::time::Duration::from_secs(config.db_connect_backoff_secs).min(max_backoff);
+
+    for attempt in 0..=config.db_connect_retries {
+        match sqlx::migrate!("./backend/migrations").run(pool).await {
+            Ok(()) => return,
+            Err(sqlx::migrate::MigrateError::Execute(e)) if is_connection_error(&e) && attempt < config.db_connect_retries => {
+                tracing::warn!(
+                    "Database not ready ({}), retrying in {}s (attempt {}/{})",
+                    e,
+                    backoff.as_secs(),
+                    attempt + 1,
+                    config.db_connect_retries
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2).min(max_backoff);
+            }
+            Err(e @ sqlx::migrate::MigrateError::Execute(_)) if attempt > 0 => {
+                tracing::error!("Could not run database migrations after {} retries: {}", attempt, e);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                tracing::error!("Could not run database migrations: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -13,6 +13,12 @@ pub struct AppState {
     pub openai_api_key: String,
     // reusable http client for outbound API calls
     pub http_client: reqwest::Client,
+    // optional runtime configuration (read from the environment)
+    pub config: crate::config::Config,
+    // per-IP rate limiter for account registration
+    pub registration_limiter: crate::rate_limit::RateLimiter,
+    // short-lived cache of analytics responses
+    pub analytics_cache: crate::cache::AnalyticsCache,
 }
 
 // struct for user registration
@@ -28,6 +34,53 @@ pub(crate) struct RegisterUser {
 pub(crate) struct LoginUser {
     pub identifier: String, // can be username or email
     pub password: String,
+    pub totp_code: Option<String>, // required once two-factor auth is enabled (a backup code also works)
+}
+
+// struct for updating the authenticated user's profile (only provided fields are changed)
+#[derive(serde::Deserialize)]
+pub(crate) struct UpdateUserRequest {
+    pub username: Option<String>,
+    pub timezone: Option<String>, // IANA name, e.g. "America/Vancouver"
+    pub amount_scale: Option<u32>, // decimal places allowed on new transaction amounts (0 to MAX_AMOUNT_SCALE)
+    pub monthly_summary_enabled: Option<bool>,
+    pub budget_alerts_enabled: Option<bool>,
+    pub summary_send_day: Option<u32>, // day of the month the summary goes out (1 to MAX_SUMMARY_SEND_DAY)
+}
+
+// struct for changing the authenticated user's password
+#[derive(serde::Deserialize)]
+pub(crate) struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+// struct for resetting the authenticated user's data (their current password is needed, so a stolen token alone can't)
+#[derive(serde::Deserialize)]
+pub(crate) struct ResetDataRequest {
+    pub password: String,
+}
+
+// struct for returning how much was cleared by a reset
+#[derive(serde::Serialize)]
+pub(crate) struct ResetDataResponse {
+    pub transactions: u64,
+    pub archived_transactions: u64,
+    pub budgets: u64,
+}
+
+// struct for the response when turning on two-factor auth (backup codes are only ever shown here)
+#[derive(serde::Serialize)]
+pub(crate) struct TwoFactorSetup {
+    pub secret: String, // base32, for typing into an authenticator app by hand
+    pub otpauth_uri: String, // for rendering as a QR code
+    pub backup_codes: Vec<String>,
+}
+
+// struct for confirming a two-factor action with a TOTP or backup code
+#[derive(serde::Deserialize)]
+pub(crate) struct TwoFactorCodeRequest {
+    pub code: String,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -43,25 +96,187 @@ pub(crate) enum TransactionKind {
     Expense,
 }
 
+// enum for transaction status (pending until it clears at the bank)
+#[derive(serde::Deserialize, serde::Serialize)]
+pub enum TransactionStatus {
+    Pending,
+    Cleared,
+}
+
 // struct for adding a transaction (request body - no user_id)
 #[derive(serde::Deserialize)]
 pub(crate) struct AddTransactionRequest {
     pub amount: Decimal,
     pub kind: TransactionKind,
     pub category: Option<String>,
-    pub date: chrono::NaiveDate,
+    pub date: Option<chrono::NaiveDate>, // defaults to today
     pub description: Option<String>,
+    pub source: Option<String>, // where income came from (e.g. "Salary"), income only
+    pub status: Option<TransactionStatus>, // defaults to Cleared
+    pub exclude_from_budget: Option<bool>, // kept on record but left out of budget progress (e.g. reimbursed expenses), defaults to false
+    pub merchant: Option<String>, // who was paid (e.g. "Starbucks")
+    pub location: Option<String>, // free text, e.g. "Vancouver, BC"
 }
 
-// struct for transaction response
+// struct for transaction response (Deserialize is only for clients and tests reading responses back;
+// no route takes a Transaction, or any other body with a user_id, since the user always comes from the token)
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Transaction {
+    pub id: uuid::Uuid,
     pub user_id: uuid::Uuid,
     pub amount: Decimal,
     pub kind: TransactionKind,
     pub category: Option<String>,
     pub date: chrono::NaiveDate,
     pub description: Option<String>,
+    pub source: Option<String>,
+    pub status: TransactionStatus,
+    pub exclude_from_budget: bool,
+    pub merchant: Option<String>,
+    pub location: Option<String>,
+    pub seq: i64, // the user's own running number for the transaction, starting at 1
+    pub reference: String, // seq formatted for people, e.g. "TXN-00042"
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+// columns transactions can be sorted by
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransactionOrderBy {
+    Date,
+    Amount,
+    CreatedAt,
+}
+
+// sort direction
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SortDirection {
+    Asc,
+    Desc,
+}
+
+// query params for listing transactions (optional status filter, sorted newest first by default)
+#[derive(serde::Deserialize)]
+pub(crate) struct TransactionQuery {
+    pub status: Option<TransactionStatus>,
+    pub order_by: Option<TransactionOrderBy>, // defaults to date
+    pub order_dir: Option<SortDirection>, // defaults to desc
+    pub modified_since: Option<chrono::DateTime<chrono::Utc>>, // for sync: only what changed after this, deletions included
+}
+
+// struct for a transaction that has been deleted (or archived) since a sync, so the client can drop its copy
+#[derive(serde::Serialize)]
+pub(crate) struct DeletedTransaction {
+    pub id: uuid::Uuid,
+    pub deleted: bool, // always true, to tell tombstones apart from live transactions
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+// one entry in a sync response: a transaction added or changed since the given time, or a tombstone for a removed one
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub(crate) enum TransactionChange {
+    Changed(Transaction),
+    Deleted(DeletedTransaction),
+}
+
+// struct for the span of a user's transactions (both dates None when there are none)
+#[derive(serde::Serialize)]
+pub(crate) struct TransactionRange {
+    pub earliest: Option<chrono::NaiveDate>,
+    pub latest: Option<chrono::NaiveDate>,
+    pub count: i64,
+}
+
+// struct for duplicating a transaction (body is optional, the copy is dated today by default)
+#[derive(serde::Deserialize)]
+pub(crate) struct DuplicateTransactionRequest {
+    pub date: Option<chrono::NaiveDate>,
+}
+
+// struct for copying one month's transactions into another (any day in each month works)
+#[derive(serde::Deserialize)]
+pub(crate) struct CloneMonthRequest {
+    pub from_month: chrono::NaiveDate,
+    pub to_month: chrono::NaiveDate,
+}
+
+// struct for a copied transaction whose day didn't exist in the target month
+#[derive(serde::Serialize)]
+pub(crate) struct ClampedDate {
+    pub original: chrono::NaiveDate,
+    pub date: chrono::NaiveDate, // the last day of the target month
+}
+
+// struct for the result of copying a month's transactions
+#[derive(serde::Serialize)]
+pub(crate) struct CloneMonthResponse {
+    pub created: usize,
+    pub clamped: Vec<ClampedDate>,
+}
+
+// struct for recategorizing a batch of transactions (request body - no user_id)
+#[derive(serde::Deserialize)]
+pub(crate) struct RecategorizeRequest {
+    pub ids: Vec<uuid::Uuid>,
+    pub category: Option<String>, // None clears the category
+}
+
+// struct for returning how many transactions were recategorized
+#[derive(serde::Serialize)]
+pub(crate) struct RecategorizeResponse {
+    pub updated: u64,
+}
+
+// struct for creating a category rule (request body - no user_id)
+#[derive(serde::Deserialize)]
+pub(crate) struct CreateCategoryRuleRequest {
+    pub match_contains: String, // matched case-insensitively anywhere in a transaction's description
+    pub category: String,
+    #[serde(default)]
+    pub priority: i32, // lower runs first, defaults to 0
+}
+
+// struct for returning a category rule
+#[derive(serde::Serialize)]
+pub(crate) struct CategoryRule {
+    pub id: uuid::Uuid,
+    pub match_contains: String,
+    pub category: String,
+    pub priority: i32,
+}
+
+// struct for applying category rules (body is optional, only uncategorized transactions are touched by default)
+#[derive(serde::Deserialize)]
+pub(crate) struct ApplyRulesRequest {
+    #[serde(default)]
+    pub all: bool, // also recategorize transactions that already have a category
+}
+
+// struct for bulk deleting transactions, either by id or by filter (request body - no user_id)
+#[derive(serde::Deserialize)]
+pub(crate) struct BulkDeleteRequest {
+    pub ids: Option<Vec<uuid::Uuid>>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub category: Option<String>,
+    #[serde(default)]
+    pub confirm: bool, // must be true, to guard against accidental mass deletion
+}
+
+// struct for returning how many transactions were deleted
+#[derive(serde::Serialize)]
+pub(crate) struct BulkDeleteResponse {
+    pub deleted: u64,
+}
+
+// query params for exporting transactions (all filters optional, dates inclusive)
+#[derive(serde::Deserialize)]
+pub(crate) struct TransactionExportQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub category: Option<String>,
 }
 
 // struct for adding/updating a budget (request body - no user_id)
@@ -70,6 +285,33 @@ pub(crate) struct UpsertBudgetRequest {
     pub month: chrono::NaiveDate, // first day of month (e.g., 2026-01-01)
     pub category: String,
     pub amount: Decimal,
+    #[serde(flatten)]
+    pub limits: BudgetLimits, // optional, and cleared when left out
+}
+
+// struct for spending limits: a soft threshold that only warns, and a hard cap (both optional)
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+pub(crate) struct BudgetLimits {
+    pub warn_at: Option<Decimal>,
+    pub cap_at: Option<Decimal>,
+}
+
+// struct for setting a month's overall spending limits, across all categories
+#[derive(serde::Deserialize)]
+pub(crate) struct UpsertMonthlyLimitsRequest {
+    pub month: chrono::NaiveDate, // any day in the month works
+    #[serde(flatten)]
+    pub limits: BudgetLimits,
+}
+
+// struct for a month's overall spend against its overall limits
+#[derive(serde::Serialize)]
+pub(crate) struct MonthlyLimitStatus {
+    pub month: chrono::NaiveDate,
+    pub spent: Decimal,
+    pub limits: BudgetLimits,
+    pub warn_crossed: bool,
+    pub cap_exceeded: bool,
 }
 
 // struct for budget response
@@ -81,12 +323,118 @@ pub(crate) struct Budget {
     pub amount: Decimal,
 }
 
+// struct for creating a household (the creator becomes its first member)
+#[derive(serde::Deserialize)]
+pub(crate) struct CreateHouseholdRequest {
+    pub name: String,
+}
+
+// struct for returning a household
+#[derive(serde::Serialize)]
+pub(crate) struct Household {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// struct for inviting an existing user to a household, by username (they only join once they accept)
+#[derive(serde::Deserialize)]
+pub(crate) struct AddHouseholdMemberRequest {
+    pub username: String,
+}
+
+// struct for returning a pending household invite
+#[derive(serde::Serialize)]
+pub(crate) struct HouseholdInvite {
+    pub household_id: uuid::Uuid,
+    pub household_name: String,
+    pub invited_by: String, // username of the member who sent it
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// struct for creating or updating a household's shared budget
+#[derive(serde::Deserialize)]
+pub(crate) struct UpsertHouseholdBudgetRequest {
+    pub month: chrono::NaiveDate, // any day in the month works
+    pub category: String,
+    pub amount: Decimal,
+}
+
+// struct for copying budgets into a month (from the previous month unless `from` is given)
+#[derive(serde::Deserialize)]
+pub(crate) struct CopyBudgetsRequest {
+    pub month: chrono::NaiveDate, // target month, any day in it works
+    pub from: Option<chrono::NaiveDate>,
+}
+
+// struct for the result of copying budgets
+#[derive(serde::Serialize)]
+pub(crate) struct CopyBudgetsResponse {
+    pub copied: i64,
+    pub skipped_existing: i64, // categories that already had a budget in the target month (left untouched)
+}
+
+// query params for generating budgets from average spend (the `months` before target_month, which defaults to the current month)
+#[derive(serde::Deserialize)]
+pub(crate) struct BudgetsFromAveragesQuery {
+    pub months: Option<u32>, // defaults to DEFAULT_AVERAGE_MONTHS, clamped to 1..=MAX_BUDGET_HISTORY_MONTHS
+    pub target_month: Option<chrono::NaiveDate>, // any day in the month works
+}
+
+// query params for suggesting a budget for one category (month defaults to the current month)
+#[derive(serde::Deserialize)]
+pub(crate) struct BudgetSuggestionQuery {
+    pub category: String,
+    pub month: Option<chrono::NaiveDate>, // any day in the month works
+}
+
+// where a suggested budget came from: the category's own spend, or the average across the user's other categories
+#[derive(serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SuggestionBasis {
+    History,
+    Default,
+}
+
+// struct for returning a suggested budget
+#[derive(serde::Serialize)]
+pub(crate) struct BudgetSuggestion {
+    pub category: String,
+    pub month: chrono::NaiveDate,
+    pub suggested_amount: Decimal,
+    pub basis: SuggestionBasis,
+    pub sample_months: i64, // months in the window the category had spending in (0 for the default)
+}
+
 // query params for budgets (optional month filter)
 #[derive(serde::Deserialize)]
 pub(crate) struct BudgetQuery {
     pub month: Option<chrono::NaiveDate>,
 }
 
+// query params for budget progress (optional month, and an optional case-insensitive category prefix)
+#[derive(serde::Deserialize)]
+pub(crate) struct BudgetProgressQuery {
+    pub month: Option<chrono::NaiveDate>,
+    pub category: Option<String>,
+    pub household_id: Option<uuid::Uuid>, // a household's shared budgets, spent against by all its members
+}
+
+// query params for one category's budget history (the last `months` months, up to and including this one)
+#[derive(serde::Deserialize)]
+pub(crate) struct BudgetHistoryQuery {
+    pub category: String,
+    pub months: Option<u32>, // defaults to 6, clamped to 1..=MAX_BUDGET_HISTORY_MONTHS
+}
+
+// struct for one month of a category's budget history (budgeted is None for months with no budget set)
+#[derive(serde::Serialize)]
+pub(crate) struct BudgetHistoryPoint {
+    pub month: chrono::NaiveDate,
+    pub budgeted: Option<Decimal>,
+    pub spent: Decimal,
+}
+
 // struct for returning budget progress (budget vs spent)
 #[derive(serde::Serialize)]
 pub(crate) struct BudgetProgress {
@@ -94,6 +442,302 @@ pub(crate) struct BudgetProgress {
     pub budget_amount: Decimal,
     pub spent: Decimal,
     pub remaining: Decimal,
+    pub pace_warning: bool, // spent a bigger share of the budget than of the month so far
+    pub limits: BudgetLimits,
+    pub warn_crossed: bool, // spent at least warn_at
+    pub cap_exceeded: bool, // spent more than cap_at
+}
+
+// struct for a budget that was overspent, and by how much
+#[derive(serde::Serialize)]
+pub(crate) struct BudgetOverrun {
+    pub category: String,
+    pub budget: Decimal,
+    pub spent: Decimal,
+    pub overage: Decimal,
+}
+
+// how a month's total spend compares with its total budget
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AdherenceStatus {
+    Under,    // spent less than ADHERENCE_ON_TRACK_PERCENT of the budget
+    OnTrack,  // spent at least that much, without going over
+    Over,     // spent more than the budget
+    NoBudget, // nothing is budgeted for the month
+}
+
+// struct for returning a month's overall budget adherence, summed over its budget progress
+#[derive(serde::Serialize)]
+pub(crate) struct BudgetAdherence {
+    pub month: chrono::NaiveDate,
+    pub budgeted: Decimal,
+    pub spent: Decimal,
+    pub percent_spent: Option<Decimal>, // spent as a percentage of budgeted, to one decimal place (None with no budgets)
+    pub status: AdherenceStatus,
+}
+
+// formats a monthly report can be exported in
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ReportFormat {
+    Json,
+    Csv,
+}
+
+// query params for exporting a monthly report (JSON unless asked for CSV)
+#[derive(serde::Deserialize)]
+pub(crate) struct ReportExportQuery {
+    pub format: Option<ReportFormat>,
+}
+
+// struct for a month's full report, as exported in JSON
+#[derive(serde::Serialize)]
+pub(crate) struct MonthlyReport {
+    pub month: chrono::NaiveDate,
+    pub transactions: Vec<Transaction>, // oldest first
+    pub budgets: Vec<Budget>,
+    pub budget_progress: Vec<BudgetProgress>,
+}
+
+// query params for analytics over a date range (both bounds optional and inclusive)
+#[derive(serde::Deserialize)]
+pub(crate) struct DateRangeQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+}
+
+// struct for returning total income per source
+#[derive(serde::Serialize)]
+pub(crate) struct IncomeBySource {
+    pub source: Option<String>, // None groups income with no source set
+    pub total: Decimal,
+}
+
+// struct for returning income, expense and the share of income saved over a date range
+#[derive(serde::Serialize)]
+pub(crate) struct SavingsRate {
+    pub income: Decimal,
+    pub expense: Decimal,
+    pub saved: Decimal, // income minus expense (negative when more went out than came in)
+    pub rate: Option<Decimal>, // saved as a percentage of income, to one decimal place (None with no income)
+}
+
+// how finely to bucket the cashflow series
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CashflowInterval {
+    Day,
+    Month,
+}
+
+// query params for the cashflow series (dates are inclusive, starting_balance offsets the running total)
+#[derive(serde::Deserialize)]
+pub(crate) struct CashflowQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub interval: Option<CashflowInterval>, // defaults to month
+    pub starting_balance: Option<Decimal>, // defaults to 0
+    pub household_id: Option<uuid::Uuid>, // combine the cashflow of every member of a household
+}
+
+// query params for uncategorized spending (dates inclusive, paginated newest first)
+#[derive(serde::Deserialize)]
+pub(crate) struct UncategorizedQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub limit: Option<i64>, // defaults to 50, clamped at 200
+    pub offset: Option<i64>,
+}
+
+// struct for returning uncategorized expenses (total and count cover the whole range, not just the page)
+#[derive(serde::Serialize)]
+pub(crate) struct UncategorizedSpending {
+    pub total: Decimal,
+    pub count: i64,
+    pub transactions: Vec<Transaction>,
+}
+
+// struct for an expense flagged as an outlier, with the baseline of the rest of its category it was compared to
+#[derive(serde::Serialize)]
+pub(crate) struct Anomaly {
+    pub transaction: Transaction,
+    pub category_mean: Decimal,
+    pub category_stddev: Decimal,
+    pub z_score: Decimal, // standard deviations above the category mean
+}
+
+// query params for the largest transactions in a date range (dates inclusive)
+#[derive(serde::Deserialize)]
+pub(crate) struct LargestTransactionsQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub kind: Option<TransactionKind>, // defaults to Expense
+    pub limit: Option<i64>, // defaults to 10, clamped at 100
+}
+
+// struct for one period of net cashflow (period is the first day of the day/month bucket)
+#[derive(serde::Serialize)]
+pub(crate) struct CashflowPoint {
+    pub period: chrono::NaiveDate,
+    pub net: Decimal, // income minus expenses within the period
+    pub cumulative: Decimal, // running balance up to and including the period
+}
+
+// analytics that can be asked for in a batch, named after their routes
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum AnalyticsKind {
+    IncomeBySource,
+    Forecast,
+    Cashflow,
+    Uncategorized,
+    Largest,
+    ByMerchant,
+    Compare,
+    ByWeekday,
+    Anomalies,
+    SavingsRate,
+}
+
+// struct for one analytic in a batch (id names its result, params are the route's query params as a JSON object)
+#[derive(serde::Deserialize)]
+pub(crate) struct AnalyticsBatchItem {
+    pub id: String,
+    pub analytic: AnalyticsKind,
+    #[serde(default)]
+    pub params: Option<serde_json::Value>,
+}
+
+// struct for a batch of analytics (at most MAX_ANALYTICS_PER_BATCH)
+#[derive(serde::Deserialize)]
+pub(crate) struct AnalyticsBatchRequest {
+    pub requests: Vec<AnalyticsBatchItem>,
+}
+
+// struct for one analytic's result in a batch: its status, with the data on success or the error message otherwise
+#[derive(serde::Serialize)]
+pub(crate) struct AnalyticsBatchResult {
+    pub status: u16,
+    pub data: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+// query params for comparing spending between two periods (all dates required and inclusive)
+#[derive(serde::Deserialize)]
+pub(crate) struct CompareQuery {
+    pub period_a_from: chrono::NaiveDate,
+    pub period_a_to: chrono::NaiveDate,
+    pub period_b_from: chrono::NaiveDate,
+    pub period_b_to: chrono::NaiveDate,
+}
+
+// struct for one category's spending in two periods (category None is uncategorized spend)
+#[derive(serde::Serialize)]
+pub(crate) struct ComparisonRow {
+    pub category: Option<String>,
+    pub period_a: Decimal,
+    pub period_b: Decimal,
+    pub delta: Decimal, // period_b minus period_a
+    pub pct_change: Option<Decimal>, // delta as a percentage of period_a, None when period_a is zero
+}
+
+// struct for total spending at one merchant
+#[derive(serde::Serialize)]
+pub(crate) struct MerchantSpending {
+    pub merchant: String,
+    pub total: Decimal,
+    pub count: i64, // number of expenses
+}
+
+// struct for total spending on one day of the week
+#[derive(serde::Serialize)]
+pub(crate) struct WeekdayTotal {
+    pub weekday: chrono::Weekday, // serialized as "Mon" through "Sun"
+    pub total: Decimal,
+}
+
+// struct for one category's projected end-of-month spend (category None is uncategorized spend)
+#[derive(serde::Serialize)]
+pub(crate) struct ForecastPoint {
+    pub category: Option<String>,
+    pub spent: Decimal,
+    pub projected: Decimal,
+    pub budget: Option<Decimal>,
+    pub over_budget: bool, // projected spend exceeds the budget
+}
+
+// struct for the overall projected end-of-month spend
+#[derive(serde::Serialize)]
+pub(crate) struct ForecastTotal {
+    pub spent: Decimal,
+    pub projected: Decimal,
+    pub budget: Decimal, // sum of all budgets for the month
+    pub over_budget: bool,
+}
+
+// struct for returning a month's spending forecast
+#[derive(serde::Serialize)]
+pub(crate) struct SpendingForecast {
+    pub month: chrono::NaiveDate,
+    pub days_elapsed: i64,
+    pub days_in_month: i64,
+    pub categories: Vec<ForecastPoint>,
+    pub total: ForecastTotal,
+}
+
+// how to order the user's categories for the category picker
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CategoryOrder {
+    Recent,   // most recently used first
+    Frequent, // most used first
+}
+
+// query params for recent categories
+#[derive(serde::Deserialize)]
+pub(crate) struct RecentCategoriesQuery {
+    pub limit: Option<i64>, // defaults to 10, clamped at 50
+    pub order: Option<CategoryOrder>, // defaults to recent
+}
+
+// struct for returning how a category has been used
+#[derive(serde::Serialize)]
+pub(crate) struct CategoryUsage {
+    pub category: String,
+    pub last_used: chrono::NaiveDate,
+    pub uses: i64,
+}
+
+// query params for description suggestions
+#[derive(serde::Deserialize)]
+pub(crate) struct DescriptionSuggestionsQuery {
+    pub prefix: String, // matched case-insensitively against the start of the description
+    pub limit: Option<i64>, // defaults to 10, clamped at 50
+}
+
+// struct for returning a past description and how often it's been used
+#[derive(serde::Serialize)]
+pub(crate) struct DescriptionUsage {
+    pub description: String,
+    pub uses: i64,
+    pub last_used: chrono::NaiveDate,
+}
+
+// struct for one category's budget and spend in a month (budget is None when no budget is set)
+#[derive(serde::Serialize)]
+pub(crate) struct CategoryOverview {
+    pub category: String,
+    pub budget: Option<Decimal>,
+    pub spent: Decimal,
+}
+
+// struct for the root path response in API-only deployments
+#[derive(serde::Serialize)]
+pub(crate) struct ApiInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub api: &'static str, // where the API routes live (there are no hosted API docs to point at instead)
 }
 
 // struct for JWT claims
@@ -101,11 +745,64 @@ pub(crate) struct BudgetProgress {
 pub(crate) struct Claims {
     pub sub: String, // we store the user ID as a string in the JWT claims
     pub exp: usize, // expiration time as a unix timestamp
+    #[serde(default)]
+    pub iat: Option<usize>, // issued-at time as a unix timestamp (missing on tokens issued before it was added)
+}
+
+// struct for the contents of a verified JWT
+pub struct VerifiedToken {
+    pub user_id: uuid::Uuid,
+    pub issued_at: Option<usize>,
+    pub expires_at: usize,
 }
 
 // struct for an authenticated user (for extracting user ID from JWT in protected routes)
+#[derive(Clone)]
 pub(crate) struct AuthenticatedUser {
     pub user_id: uuid::Uuid,
+    pub issued_at: Option<usize>,
+    pub expires_at: usize,
+}
+
+// struct for describing the caller's current session (timestamps are unix seconds)
+#[derive(serde::Serialize)]
+pub(crate) struct SessionInfo {
+    pub user_id: uuid::Uuid,
+    pub issued_at: Option<usize>,
+    pub expires_at: usize,
+    pub expires_in: u64, // seconds until the token expires
+}
+
+// struct for confirming a token is still valid, and until when
+#[derive(serde::Serialize)]
+pub(crate) struct TokenValidation {
+    pub valid: bool, // always true; an invalid or expired token gets the extractor's 401 instead
+    pub user_id: uuid::Uuid,
+    pub expires_at: usize,
+}
+
+// struct for the caller's own account details
+#[derive(serde::Serialize)]
+pub(crate) struct UserProfile {
+    pub user_id: uuid::Uuid,
+    pub username: String,
+    pub email: String,
+    pub two_factor_enabled: bool,
+    pub amount_scale: u32,
+    pub timezone: String,
+    pub monthly_summary_enabled: bool,
+    pub budget_alerts_enabled: bool,
+    pub summary_send_day: u32,
+}
+
+// struct for everything the frontend needs to render on load with a stored token, in one response
+#[derive(serde::Serialize)]
+pub(crate) struct BootstrapResponse {
+    pub profile: UserProfile,
+    pub session: SessionInfo,
+    pub budgets: Vec<Budget>, // the current month's
+    pub budget_progress: Vec<BudgetProgress>, // the current month's
+    pub recent_transactions: Vec<Transaction>, // newest first, at most BOOTSTRAP_RECENT_TRANSACTIONS
 }
 
 // struct for transaction embedding
@@ -150,4 +847,29 @@ pub(crate) struct SemanticSearchRequest {
 
 /* constants */
 
-pub(crate) const JWT_EXPIRATION_HOURS: i64 = 24; // JWT expiration time in hours
+pub(crate) const TOTP_BACKUP_CODE_COUNT: usize = 8; // backup codes handed out when enabling two-factor auth
+pub(crate) const BOOTSTRAP_RECENT_TRANSACTIONS: usize = 20; // transactions included in the bootstrap response
+pub(crate) const MAX_BUDGET_HISTORY_MONTHS: u32 = 24; // longest budget history served in one request
+pub(crate) const DEFAULT_AVERAGE_MONTHS: u32 = 3; // trailing months budgets are averaged over unless asked otherwise
+pub(crate) const RETRY_AFTER_SECS: u64 = 5; // Retry-After sent with 503s (busy server or database unavailable)
+pub(crate) const SPENDING_WARNING_HEADER: &str = "x-spending-warning"; // set when a new expense crosses a soft limit
+pub(crate) const MAX_ANALYTICS_PER_BATCH: usize = 10; // most analytics one batch request can ask for
+pub(crate) const ANOMALY_Z_SCORE: i32 = 2; // standard deviations above the category mean that count as an anomaly
+pub(crate) const ADHERENCE_ON_TRACK_PERCENT: i64 = 90; // share of a month's budget spent from which it's on track
+pub(crate) const MIN_ANOMALY_SAMPLE_SIZE: i64 = 5; // other expenses a category needs before anything in it can be flagged
+
+// maximum lengths (in characters) for free-text fields
+pub(crate) const MAX_USERNAME_LENGTH: usize = 50; // matches the users.username column
+pub(crate) const MAX_EMAIL_LENGTH: usize = 254; // the longest address RFC 5321 allows
+pub(crate) const MAX_CATEGORY_LENGTH: usize = 64;
+pub(crate) const MAX_HOUSEHOLD_NAME_LENGTH: usize = 100;
+pub(crate) const MAX_DESCRIPTION_LENGTH: usize = 1000;
+pub(crate) const MAX_MERCHANT_LENGTH: usize = 100;
+pub(crate) const MAX_LOCATION_LENGTH: usize = 200;
+pub(crate) const MAX_RULE_MATCH_LENGTH: usize = 100;
+
+// bounds on amounts (budgets are NUMERIC(15, 2), transaction amounts are unconstrained NUMERIC checked against these)
+pub(crate) const MAX_AMOUNT_WHOLE_DIGITS: u32 = 13; // digits before the decimal point
+pub(crate) const DEFAULT_AMOUNT_SCALE: u32 = 2; // decimal places, unless the user has asked for more
+pub(crate) const MAX_AMOUNT_SCALE: u32 = 8; // enough for satoshis
+pub(crate) const MAX_SUMMARY_SEND_DAY: u32 = 28; // the last day every month has
@@ -0,0 +1,142 @@
+/* at-rest encryption of free-text transaction fields (description, merchant) */
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hkdf;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::config::Config;
+
+// marks a stored value as ciphertext; anything without it is legacy plaintext and is returned as is
+pub const ENCRYPTED_NOTE_PREFIX: &str = "enc:v1:";
+
+// fixed HKDF salt, so the same secret always derives the same per-user keys
+const KEY_DERIVATION_SALT: &[u8] = b"financetracker notes v1";
+
+// derive a user's AES-256-GCM key from the server secret, so one user's key can't open another's notes
+fn user_key(secret: &str, user_id: uuid::Uuid) -> LessSafeKey {
+    let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, KEY_DERIVATION_SALT).extract(secret.as_bytes());
+    let info = [user_id.as_bytes().as_slice()];
+    let okm = prk.expand(&info, &AES_256_GCM).expect("AES-256 key length is valid for HKDF-SHA256");
+
+    LessSafeKey::new(UnboundKey::from(okm))
+}
+
+// encrypt a note for a user, returning the prefixed base64 of nonce + ciphertext
+pub fn encrypt_note(secret: &str, user_id: uuid::Uuid, plaintext: &str) -> String {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).expect("system randomness is available");
+
+    let mut sealed = plaintext.as_bytes().to_vec();
+    user_key(secret, user_id)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(user_id.as_bytes()), &mut sealed)
+        .expect("notes are far below the AES-GCM length limit");
+
+    let mut payload = nonce.to_vec();
+    payload.extend(sealed);
+    format!("{}{}", ENCRYPTED_NOTE_PREFIX, BASE64.encode(payload))
+}
+
+// decrypt a stored note for a user; legacy plaintext comes back unchanged, and None means the value looked
+// encrypted but couldn't be opened (wrong secret, wrong user or a corrupted value)
+pub fn decrypt_note(secret: &str, user_id: uuid::Uuid, stored: &str) -> Option<String> {
+    let Some(encoded) = stored.strip_prefix(ENCRYPTED_NOTE_PREFIX) else {
+        return Some(stored.to_string());
+    };
+
+    let payload = BASE64.decode(encoded).ok()?;
+    if payload.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, sealed) = payload.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+
+    let mut sealed = sealed.to_vec();
+    let plaintext = user_key(secret, user_id)
+        .open_in_place(nonce, Aad::from(user_id.as_bytes()), &mut sealed)
+        .ok()?;
+
+    String::from_utf8(plaintext.to_vec()).ok()
+}
+
+// helper function to prepare a note for storage, encrypting it when ENCRYPT_NOTES is on
+pub fn seal_note(config: &Config, user_id: uuid::Uuid, note: Option<&str>) -> Option<String> {
+    let note = note?;
+    match (config.encrypt_notes, config.notes_encryption_secret.as_deref()) {
+        (true, Some(secret)) => Some(encrypt_note(secret, user_id, note)),
+        _ => Some(note.to_string()),
+    }
+}
+
+// helper function to read a stored note back; encrypted notes are decrypted whenever the secret is configured
+// (even with ENCRYPT_NOTES turned off again), and ones that can't be opened are returned as stored
+pub fn open_note(config: &Config, user_id: uuid::Uuid, stored: Option<String>) -> Option<String> {
+    let stored = stored?;
+    match config.notes_encryption_secret.as_deref() {
+        Some(secret) if stored.starts_with(ENCRYPTED_NOTE_PREFIX) => {
+            Some(decrypt_note(secret, user_id, &stored).unwrap_or(stored))
+        }
+        _ => Some(stored),
+    }
+}
+
+// unit tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-notes-secret";
+
+    // test that a note survives an encrypt/decrypt round trip and isn't stored as plaintext
+    #[test]
+    fn test_round_trip() {
+        let user_id = uuid::Uuid::new_v4();
+        let stored = encrypt_note(SECRET, user_id, "Coffee with Sam");
+
+        assert!(stored.starts_with(ENCRYPTED_NOTE_PREFIX));
+        assert!(!stored.contains("Coffee"));
+        assert_eq!(decrypt_note(SECRET, user_id, &stored).as_deref(), Some("Coffee with Sam"));
+    }
+
+    // test that encrypting the same note twice gives different ciphertexts (fresh nonce each time)
+    #[test]
+    fn test_nonce_is_random() {
+        let user_id = uuid::Uuid::new_v4();
+        assert_ne!(encrypt_note(SECRET, user_id, "Rent"), encrypt_note(SECRET, user_id, "Rent"));
+    }
+
+    // test that legacy plaintext values pass through unchanged
+    #[test]
+    fn test_legacy_plaintext() {
+        let user_id = uuid::Uuid::new_v4();
+        assert_eq!(decrypt_note(SECRET, user_id, "Groceries").as_deref(), Some("Groceries"));
+    }
+
+    // test that another user's key (or another secret) can't open a note
+    #[test]
+    fn test_wrong_key_fails() {
+        let user_id = uuid::Uuid::new_v4();
+        let stored = encrypt_note(SECRET, user_id, "Salary");
+
+        assert_eq!(decrypt_note(SECRET, uuid::Uuid::new_v4(), &stored), None);
+        assert_eq!(decrypt_note("another-secret", user_id, &stored), None);
+    }
+
+    // test that sealing only encrypts when the flag and secret are both set, and opening handles either form
+    #[test]
+    fn test_seal_and_open_follow_config() {
+        let user_id = uuid::Uuid::new_v4();
+        let mut config = Config::default();
+
+        assert_eq!(seal_note(&config, user_id, Some("Lunch")).as_deref(), Some("Lunch"));
+
+        config.encrypt_notes = true;
+        config.notes_encryption_secret = Some(SECRET.to_string());
+        let sealed = seal_note(&config, user_id, Some("Lunch"));
+        assert!(sealed.as_deref().unwrap().starts_with(ENCRYPTED_NOTE_PREFIX));
+        assert_eq!(open_note(&config, user_id, sealed).as_deref(), Some("Lunch"));
+        assert_eq!(open_note(&config, user_id, Some("Lunch".to_string())).as_deref(), Some("Lunch"));
+        assert_eq!(seal_note(&config, user_id, None), None);
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/* rate limiting */
+
+// simple in-memory sliding-window rate limiter keyed by client IP
+// (requests with no known IP share a single bucket)
+#[derive(Clone)]
+pub struct RateLimiter {
+    max_requests: usize, // 0 disables the limit
+    window: Duration,
+    hits: Arc<Mutex<HashMap<Option<IpAddr>, Vec<Instant>>>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: usize, window: Duration) -> Self {
+        RateLimiter {
+            max_requests,
+            window,
+            hits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // limiter for the registration endpoint, using the configured threshold and window
+    pub fn for_registration(config: &Config) -> Self {
+        RateLimiter::new(
+            config.registration_rate_limit,
+            Duration::from_secs(config.registration_rate_window_secs),
+        )
+    }
+
+    // records a request from the given IP, returning false if it's over the limit
+    pub fn check(&self, ip: Option<IpAddr>) -> bool {
+        if self.max_requests == 0 {
+            return true;
+        }
+
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap();
+
+        // forget requests that have aged out of the window (and IPs with nothing left)
+        hits.retain(|_, times| {
+            times.retain(|t| now.duration_since(*t) < self.window);
+            !times.is_empty()
+        });
+
+        let times = hits.entry(ip).or_default();
+        if times.len() >= self.max_requests {
+            return false;
+        }
+
+        times.push(now);
+        true
+    }
+}
+
+/* helper functions */
+
+// helper function to work out the client's IP, from X-Forwarded-For when we're configured to trust a proxy,
+// otherwise from the socket address of the connection
+pub(crate) fn client_ip(
+    headers: &axum::http::HeaderMap,
+    connect_info: Option<SocketAddr>,
+    trust_forwarded_for: bool,
+) -> Option<IpAddr> {
+    if trust_forwarded_for {
+        // the left-most address is the original client
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+
+    connect_info.map(|addr| addr.ip())
+}
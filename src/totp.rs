@@ -0,0 +1,124 @@
+use hmac::{Hmac, Mac};
+
+/* two-factor authentication (TOTP, RFC 6238) */
+
+type HmacSha1 = Hmac<sha1::Sha1>;
+
+const TOTP_STEP_SECS: u64 = 30; // each code is valid for a 30 second window
+const TOTP_DIGITS: u32 = 6;
+const TOTP_ISSUER: &str = "FinanceTracker";
+
+// computes the HOTP code (RFC 4226) for a secret and counter
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // dynamic truncation: the low 4 bits of the last byte pick where to read 31 bits from
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+// computes the 6-digit TOTP code for a secret at the given unix time
+pub fn totp_code(secret: &[u8], unix_time: u64) -> String {
+    format!("{:06}", hotp(secret, unix_time / TOTP_STEP_SECS))
+}
+
+// checks a submitted code against the secret, allowing one step either side for clock drift
+pub fn verify_totp(secret: &[u8], code: &str, unix_time: u64) -> bool {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    let counter = unix_time / TOTP_STEP_SECS;
+    [counter.saturating_sub(1), counter, counter + 1]
+        .iter()
+        .any(|c| format!("{:06}", hotp(secret, *c)) == code)
+}
+
+// builds the otpauth:// provisioning URI that authenticator apps read from a QR code
+pub fn provisioning_uri(secret: &[u8], account_name: &str) -> String {
+    let mut uri = reqwest::Url::parse(&format!("otpauth://totp/{}:{}", TOTP_ISSUER, account_name))
+        .expect("otpauth URI is always valid");
+
+    uri.query_pairs_mut()
+        .append_pair("secret", &base32_encode(secret))
+        .append_pair("issuer", TOTP_ISSUER)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &TOTP_DIGITS.to_string())
+        .append_pair("period", &TOTP_STEP_SECS.to_string());
+
+    uri.to_string()
+}
+
+// encodes bytes as unpadded base32 (RFC 4648), the format authenticator apps expect secrets in
+pub fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut output = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            output.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+
+    // flush any leftover bits, padded with zeros on the right
+    if bits > 0 {
+        output.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    output
+}
+
+// unit test
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // check against the RFC 4226 / RFC 6238 test vectors for the secret "12345678901234567890"
+    #[test]
+    fn test_totp_rfc_vectors() {
+        let secret = b"12345678901234567890";
+
+        assert_eq!(hotp(secret, 0), 755224);
+        assert_eq!(hotp(secret, 1), 287082);
+        assert_eq!(totp_code(secret, 59), "287082");
+        assert_eq!(totp_code(secret, 1111111109), "081804");
+    }
+
+    // check that codes from the neighbouring windows are accepted, but not ones further away
+    #[test]
+    fn test_verify_totp_drift_window() {
+        let secret = b"12345678901234567890";
+        let now = 1_700_000_000;
+
+        assert!(verify_totp(secret, &totp_code(secret, now), now));
+        assert!(verify_totp(secret, &totp_code(secret, now - 30), now));
+        assert!(verify_totp(secret, &totp_code(secret, now + 30), now));
+        assert!(!verify_totp(secret, &totp_code(secret, now + 90), now));
+        assert!(!verify_totp(secret, "12345", now));
+    }
+
+    // check base32 against the RFC 4648 test vectors (without padding)
+    #[test]
+    fn test_base32_encode() {
+        assert_eq!(base32_encode(b""), "");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    }
+}
@@ -0,0 +1,571 @@
+mod common;
+
+use financetracker::build_app;
+
+// use the test module
+#[cfg(test)]
+mod analytics_tests {
+    use super::*;
+
+    // helper function to tag a seeded transaction with an income source
+    async fn set_source(state: &financetracker::AppState, transaction_id: uuid::Uuid, source: &str) {
+        sqlx::query("UPDATE transactions SET source = $1 WHERE id = $2")
+            .bind(source)
+            .bind(transaction_id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+    }
+
+    // test that income is broken down per source, ignoring expenses
+    #[tokio::test]
+    async fn test_income_by_source_breakdown() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let salary1 = common::insert_test_transaction(&state, user_id, "3000.00", "income", None, "2026-01-15", None).await;
+        let salary2 = common::insert_test_transaction(&state, user_id, "3000.00", "income", None, "2026-01-31", None).await;
+        let freelance = common::insert_test_transaction(&state, user_id, "450.00", "income", None, "2026-01-20", None).await;
+        common::insert_test_transaction(&state, user_id, "80.00", "income", None, "2026-01-22", Some("Birthday money")).await;
+        common::insert_test_transaction(&state, user_id, "99.00", "expense", Some("Food"), "2026-01-20", None).await;
+
+        set_source(&state, salary1, "Salary").await;
+        set_source(&state, salary2, "Salary").await;
+        set_source(&state, freelance, "Freelance").await;
+
+        let (status, breakdown) = common::send_request(&app, "GET", "/api/analytics/income-by-source?from=2026-01-01&to=2026-01-31", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        // largest source first, with source-less income grouped under null
+        assert_eq!(breakdown, serde_json::json!([
+            { "source": "Salary", "total": "6000.00" },
+            { "source": "Freelance", "total": "450.00" },
+            { "source": null, "total": "80.00" },
+        ]));
+    }
+
+    // test the savings rate over a window with known income and expense, and a null rate for a window with no income
+    #[tokio::test]
+    async fn test_savings_rate() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "4000.00", "income", None, "2026-01-15", None).await;
+        common::insert_test_transaction(&state, user_id, "1000.00", "expense", Some("Rent"), "2026-01-01", None).await;
+        common::insert_test_transaction(&state, user_id, "500.00", "expense", Some("Food"), "2026-01-20", None).await;
+        common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Food"), "2026-02-03", None).await;
+
+        // (4000 - 1500) / 4000 = 62.5%
+        let (status, january) = common::send_request(&app, "GET", "/api/analytics/savings-rate?from=2026-01-01&to=2026-01-31", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(january, serde_json::json!({ "income": "4000.00", "expense": "1500.00", "saved": "2500.00", "rate": "62.5" }));
+
+        let (status, february) = common::send_request(&app, "GET", "/api/analytics/savings-rate?from=2026-02-01&to=2026-02-28", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(february["saved"], "-20.00");
+        assert_eq!(february["rate"], serde_json::Value::Null);
+    }
+
+    // test that a planned expense dated after today stays out of the savings rate and cashflow until its date comes
+    #[tokio::test]
+    async fn test_analytics_leave_out_planned_transactions() {
+        let mut state = common::setup_app_state().await;
+        state.config.allow_future_transactions = true;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let today = chrono::Utc::now().date_naive();
+        let planned = today + chrono::Duration::days(40);
+        common::insert_test_transaction(&state, user_id, "1000.00", "income", None, &today.to_string(), None).await;
+        common::insert_test_transaction(&state, user_id, "400.00", "expense", Some("Rent"), &planned.to_string(), None).await;
+
+        let uri = format!("/api/analytics/savings-rate?from={}&to={}", today, planned);
+        let (status, savings) = common::send_request(&app, "GET", &uri, Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(savings["expense"], "0");
+        assert_eq!(savings["saved"], "1000.00");
+
+        let (status, cashflow) = common::send_request(&app, "GET", "/api/analytics/cashflow?interval=day", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(cashflow, serde_json::json!([
+            { "period": today, "net": "1000.00", "cumulative": "1000.00" },
+        ]));
+    }
+
+    // test that list and analytics endpoints return 200 with an empty array (never null) when there's no data
+    #[tokio::test]
+    async fn test_empty_results_are_empty_arrays() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // a brand new user has no transactions or budgets
+        let endpoints = [
+            "/api/transactions",
+            "/api/transactions/archived",
+            "/api/budgets",
+            "/api/budgets?month=2026-01-01",
+            "/api/budgets/progress",
+            "/api/budgets/progress?month=2026-01-01",
+            "/api/budgets/overruns",
+            "/api/analytics/income-by-source",
+            "/api/analytics/cashflow",
+            "/api/analytics/largest",
+            "/api/analytics/by-merchant",
+            "/api/analytics/anomalies",
+            "/api/households",
+            "/api/households/invites",
+            "/api/categories/overview",
+        ];
+
+        for endpoint in endpoints {
+            let (status, body) = common::send_request(&app, "GET", endpoint, Some(&access_token), None).await;
+            assert_eq!(status, axum::http::StatusCode::OK, "unexpected status for {}", endpoint);
+            assert_eq!(body, serde_json::json!([]), "unexpected body for {}", endpoint);
+        }
+    }
+
+    // test the forecast for a finished month (projection equals spend) and a future month (nothing projected yet)
+    #[tokio::test]
+    async fn test_spending_forecast() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // February 2025 is in the past and only has 28 days
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Food"), "2025-02-03", None).await;
+        common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Food"), "2025-02-27", None).await;
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", None, "2025-02-10", None).await;
+
+        let budget = serde_json::json!({ "month": "2025-02-01", "category": "Food", "amount": 50 });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(budget)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (status, forecast) = common::send_request(&app, "GET", "/api/analytics/forecast?month=2025-02-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(forecast["days_in_month"], 28);
+        assert_eq!(forecast["days_elapsed"], 28);
+
+        // categories come back alphabetically, with uncategorized spend last
+        assert_eq!(forecast["categories"], serde_json::json!([
+            { "category": "Food", "spent": "60.00", "projected": "60.00", "budget": "50.00", "over_budget": true },
+            { "category": null, "spent": "10.00", "projected": "10.00", "budget": null, "over_budget": false },
+        ]));
+        assert_eq!(forecast["total"]["spent"], "70.00");
+        assert_eq!(forecast["total"]["budget"], "50.00");
+        assert_eq!(forecast["total"]["over_budget"], true);
+
+        // a month that hasn't started yet has nothing elapsed, so nothing is projected (and nothing divides by zero)
+        let (status, future) = common::send_request(&app, "GET", "/api/analytics/forecast?month=2099-01-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(future["days_elapsed"], 0);
+        assert_eq!(future["days_in_month"], 31);
+        assert_eq!(future["categories"], serde_json::json!([]));
+        assert_eq!(future["total"]["projected"], "0");
+    }
+
+    // test the monthly and daily cashflow series, including the running balance and a starting offset
+    #[tokio::test]
+    async fn test_cashflow() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "1000.00", "income", None, "2026-01-01", None).await;
+        common::insert_test_transaction(&state, user_id, "300.00", "expense", Some("Rent"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "200.00", "expense", Some("Food"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "700.00", "expense", Some("Travel"), "2026-02-10", None).await;
+
+        let (status, monthly) = common::send_request(&app, "GET", "/api/analytics/cashflow", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(monthly, serde_json::json!([
+            { "period": "2026-01-01", "net": "500.00", "cumulative": "500.00" },
+            { "period": "2026-02-01", "net": "-700.00", "cumulative": "-200.00" },
+        ]));
+
+        let (status, daily) = common::send_request(
+            &app,
+            "GET",
+            "/api/analytics/cashflow?interval=day&to=2026-01-31&starting_balance=50",
+            Some(&access_token),
+            None,
+        ).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(daily, serde_json::json!([
+            { "period": "2026-01-01", "net": "1000.00", "cumulative": "1050.00" },
+            { "period": "2026-01-05", "net": "-500.00", "cumulative": "550.00" },
+        ]));
+    }
+
+    // test that only expenses with a missing or blank category are reported, with totals across all pages
+    #[tokio::test]
+    async fn test_uncategorized_spending() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", None, "2026-01-03", Some("no category")).await;
+        common::insert_test_transaction(&state, user_id, "15.00", "expense", Some(""), "2026-01-05", Some("blank category")).await;
+        common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Food"), "2026-01-06", Some("categorized")).await;
+        common::insert_test_transaction(&state, user_id, "500.00", "income", None, "2026-01-07", Some("income")).await;
+        common::insert_test_transaction(&state, user_id, "30.00", "expense", None, "2025-12-30", Some("out of range")).await;
+
+        let (status, report) = common::send_request(&app, "GET", "/api/analytics/uncategorized?from=2026-01-01&to=2026-01-31", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(report["total"], "25.00");
+        assert_eq!(report["count"], 2);
+        let descriptions: Vec<&str> = report["transactions"].as_array().unwrap().iter().map(|t| t["description"].as_str().unwrap()).collect();
+        assert_eq!(descriptions, vec!["blank category", "no category"]);
+
+        // a smaller page still reports the totals for the whole range
+        let (_, page) = common::send_request(&app, "GET", "/api/analytics/uncategorized?from=2026-01-01&to=2026-01-31&limit=1&offset=1", Some(&access_token), None).await;
+        assert_eq!(page["count"], 2);
+        assert_eq!(page["transactions"][0]["description"], "no category");
+        assert_eq!(page["transactions"].as_array().unwrap().len(), 1);
+    }
+
+    // test that the largest expenses in the window come back biggest first, limited to the top N
+    #[tokio::test]
+    async fn test_largest_transactions() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for (amount, description) in [("45.00", "dinner"), ("1200.00", "rent"), ("8.50", "coffee"), ("310.00", "flights")] {
+            common::insert_test_transaction(&state, user_id, amount, "expense", Some("Misc"), "2026-03-10", Some(description)).await;
+        }
+        // bigger, but income or outside the window, so left out
+        common::insert_test_transaction(&state, user_id, "5000.00", "income", None, "2026-03-01", Some("salary")).await;
+        common::insert_test_transaction(&state, user_id, "9000.00", "expense", Some("Car"), "2026-02-01", Some("car")).await;
+
+        let (status, largest) = common::send_request(
+            &app,
+            "GET",
+            "/api/analytics/largest?from=2026-03-01&to=2026-03-31&limit=3",
+            Some(&access_token),
+            None,
+        ).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let descriptions: Vec<&str> = largest.as_array().unwrap().iter().map(|t| t["description"].as_str().unwrap()).collect();
+        assert_eq!(descriptions, vec!["rent", "flights", "dinner"]);
+        assert_eq!(largest[0]["amount"], "1200.00");
+        assert_eq!(largest[0]["category"], "Misc");
+
+        let (_, income) = common::send_request(&app, "GET", "/api/analytics/largest?kind=Income", Some(&access_token), None).await;
+        assert_eq!(income[0]["description"], "salary");
+    }
+
+    // test comparing two months, including categories that only have spending in one of them
+    #[tokio::test]
+    async fn test_spending_comparison() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // january: food and travel, february: food and gym, plus income that shouldn't count
+        common::insert_test_transaction(&state, user_id, "200.00", "expense", Some("Food"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "300.00", "expense", Some("Travel"), "2026-01-20", None).await;
+        common::insert_test_transaction(&state, user_id, "250.00", "expense", Some("Food"), "2026-02-03", None).await;
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Gym"), "2026-02-28", None).await;
+        common::insert_test_transaction(&state, user_id, "1000.00", "income", None, "2026-02-01", None).await;
+
+        let (status, rows) = common::send_request(
+            &app,
+            "GET",
+            "/api/analytics/compare?period_a_from=2026-01-01&period_a_to=2026-01-31&period_b_from=2026-02-01&period_b_to=2026-02-28",
+            Some(&access_token),
+            None,
+        ).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(rows, serde_json::json!([
+            { "category": "Food", "period_a": "200.00", "period_b": "250.00", "delta": "50.00", "pct_change": "25.00" },
+            { "category": "Gym", "period_a": "0", "period_b": "40.00", "delta": "40.00", "pct_change": null },
+            { "category": "Travel", "period_a": "300.00", "period_b": "0", "delta": "-300.00", "pct_change": "-100.00" },
+        ]));
+
+        // a backwards period is rejected
+        let (status, _) = common::send_request(
+            &app,
+            "GET",
+            "/api/analytics/compare?period_a_from=2026-01-31&period_a_to=2026-01-01&period_b_from=2026-02-01&period_b_to=2026-02-28",
+            Some(&access_token),
+            None,
+        ).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that expenses are totalled per merchant, biggest first, skipping income and expenses without a merchant
+    #[tokio::test]
+    async fn test_spending_by_merchant() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let coffee1 = common::insert_test_transaction(&state, user_id, "4.50", "expense", Some("Coffee"), "2026-01-02", None).await;
+        let coffee2 = common::insert_test_transaction(&state, user_id, "5.25", "expense", Some("Coffee"), "2026-01-09", None).await;
+        let groceries = common::insert_test_transaction(&state, user_id, "62.10", "expense", Some("Food"), "2026-01-10", None).await;
+        let refund = common::insert_test_transaction(&state, user_id, "20.00", "income", None, "2026-01-11", None).await;
+        common::insert_test_transaction(&state, user_id, "15.00", "expense", Some("Misc"), "2026-01-12", None).await;
+
+        for (id, merchant) in [(coffee1, "Starbucks"), (coffee2, "Starbucks"), (groceries, "Save-On-Foods"), (refund, "Save-On-Foods")] {
+            sqlx::query("UPDATE transactions SET merchant = $2, location = 'Vancouver, BC' WHERE id = $1")
+                .bind(id)
+                .bind(merchant)
+                .execute(&state.pool)
+                .await
+                .unwrap();
+        }
+
+        let (status, merchants) = common::send_request(&app, "GET", "/api/analytics/by-merchant", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(merchants, serde_json::json!([
+            { "merchant": "Save-On-Foods", "total": "62.10", "count": 1 },
+            { "merchant": "Starbucks", "total": "9.75", "count": 2 },
+        ]));
+
+        // the range is inclusive and the transactions themselves carry the new fields
+        let (_, merchants) = common::send_request(&app, "GET", "/api/analytics/by-merchant?from=2026-01-09&to=2026-01-09", Some(&access_token), None).await;
+        assert_eq!(merchants, serde_json::json!([{ "merchant": "Starbucks", "total": "5.25", "count": 1 }]));
+
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        let coffee = transactions.as_array().unwrap().iter().find(|t| t["id"] == coffee1.to_string()).unwrap();
+        assert_eq!(coffee["merchant"], "Starbucks");
+        assert_eq!(coffee["location"], "Vancouver, BC");
+    }
+
+    // test that a clear outlier is flagged against the rest of its category, and small categories are skipped
+    #[tokio::test]
+    async fn test_anomalies() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for (day, amount) in [(2, "10.00"), (5, "12.00"), (9, "11.00"), (12, "9.00"), (16, "10.00"), (20, "13.00")] {
+            common::insert_test_transaction(&state, user_id, amount, "expense", Some("Food"), &format!("2026-02-{:02}", day), None).await;
+        }
+        let outlier = common::insert_test_transaction(&state, user_id, "250.00", "expense", Some("Food"), "2026-02-24", None).await;
+
+        // too few gym expenses to say what's normal, however lopsided they look
+        for amount in ["20.00", "20.00", "500.00"] {
+            common::insert_test_transaction(&state, user_id, amount, "expense", Some("Gym"), "2026-02-10", None).await;
+        }
+
+        let (status, anomalies) = common::send_request(&app, "GET", "/api/analytics/anomalies", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let anomalies = anomalies.as_array().unwrap();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0]["transaction"]["id"], outlier.to_string());
+        assert_eq!(anomalies[0]["category_mean"], "10.83");
+        assert_eq!(anomalies[0]["category_stddev"], "1.47");
+        let z_score: f64 = anomalies[0]["z_score"].as_str().unwrap().parse().unwrap();
+        assert!(z_score > 2.0);
+
+        // the date range applies before the baseline is worked out, leaving too little to compare against
+        let (_, anomalies) = common::send_request(&app, "GET", "/api/analytics/anomalies?from=2026-02-15", Some(&access_token), None).await;
+        assert_eq!(anomalies, serde_json::json!([]));
+    }
+
+    // test that expenses are bucketed by weekday, with empty days zero-filled and the configured week start first
+    #[tokio::test]
+    async fn test_spending_by_weekday() {
+        let mut state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // two Fridays, a Sunday and a Monday, plus income that shouldn't count
+        common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Dining"), "2026-01-02", None).await;
+        common::insert_test_transaction(&state, user_id, "35.50", "expense", Some("Dining"), "2026-01-09", None).await;
+        common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("Food"), "2026-01-04", None).await;
+        common::insert_test_transaction(&state, user_id, "8.00", "expense", Some("Food"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "500.00", "income", None, "2026-01-05", None).await;
+
+        let (status, weekdays) = common::send_request(&app, "GET", "/api/analytics/by-weekday", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(weekdays, serde_json::json!([
+            { "weekday": "Mon", "total": "8.00" },
+            { "weekday": "Tue", "total": "0" },
+            { "weekday": "Wed", "total": "0" },
+            { "weekday": "Thu", "total": "0" },
+            { "weekday": "Fri", "total": "55.50" },
+            { "weekday": "Sat", "total": "0" },
+            { "weekday": "Sun", "total": "12.00" },
+        ]));
+
+        // the date range is inclusive, and a sunday week start moves sunday to the front
+        state.config.week_start = chrono::Weekday::Sun;
+        let app = build_app(state);
+        let (_, weekdays) = common::send_request(&app, "GET", "/api/analytics/by-weekday?from=2026-01-04&to=2026-01-09", Some(&access_token), None).await;
+        let days: Vec<&str> = weekdays.as_array().unwrap().iter().map(|d| d["weekday"].as_str().unwrap()).collect();
+        assert_eq!(days, vec!["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"]);
+        assert_eq!(weekdays[0]["total"], "12.00");
+        assert_eq!(weekdays[5]["total"], "35.50");
+    }
+
+    // test that ?pretty=true indents the JSON without changing anything else, and only when the instance allows it
+    #[tokio::test]
+    async fn test_pretty_json_only_adds_whitespace() {
+        let mut state = common::setup_app_state().await;
+        state.config.allow_pretty_json = true;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // budget progress has nested objects and a string with a space in it to check against
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "Eating out", "amount": "100.00", "warn_at": "80.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("Eating out"), "2026-01-05", None).await;
+
+        let (_, _, compact) = common::send_raw_request(&app, "/api/budgets/progress?month=2026-01-01", &access_token).await;
+        let (status, headers, pretty) = common::send_raw_request(&app, "/api/budgets/progress?month=2026-01-01&pretty=true", &access_token).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert!(headers["content-type"].to_str().unwrap().starts_with("application/json"));
+
+        assert!(!compact.contains('\n'));
+        assert!(pretty.starts_with("[\n  {\n    \"category\": \"Eating out\","));
+        assert_ne!(pretty, compact);
+        let strip = |json: &str| json.split_whitespace().collect::<String>();
+        assert_eq!(strip(&pretty), strip(&compact));
+
+        // with the flag off the param is ignored
+        state.config.allow_pretty_json = false;
+        let app = build_app(state);
+        let (_, _, ignored) = common::send_raw_request(&app, "/api/budgets/progress?month=2026-01-01&pretty=true", &access_token).await;
+        assert_eq!(ignored, compact);
+    }
+
+    // test that CAMEL_CASE_JSON renames nested response keys while leaving values and batch ids alone
+    #[tokio::test]
+    async fn test_camel_case_json() {
+        let mut state = common::setup_app_state().await;
+        state.config.camel_case_json = true;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "eating_out", "amount": "100.00", "warn_at": "80.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("eating_out"), "2026-01-05", None).await;
+
+        let (status, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-01-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(progress, serde_json::json!([{
+            "category": "eating_out",
+            "budgetAmount": "100.00",
+            "spent": "12.00",
+            "remaining": "88.00",
+            "paceWarning": false,
+            "limits": { "warnAt": "80.00", "capAt": null },
+            "warnCrossed": false,
+            "capExceeded": false,
+        }]));
+
+        // the batch's own ids are kept, while the results inside them are renamed
+        let body = serde_json::json!({ "requests": [{ "id": "by_month", "analytic": "largest", "params": { "limit": 1 } }] });
+        let (status, batch) = common::send_request(&app, "POST", "/api/analytics/batch", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(batch["by_month"]["status"], 200);
+        assert!(batch["by_month"]["data"][0].get("excludeFromBudget").is_some());
+        assert!(batch["by_month"]["data"][0].get("exclude_from_budget").is_none());
+
+        // however a client spells its ids, they come back exactly as sent
+        let body = serde_json::json!({ "requests": [
+            { "id": "spend_x", "analytic": "savings-rate" },
+            { "id": "_x_Y-z", "analytic": "savings-rate" },
+        ] });
+        let (status, batch) = common::send_request(&app, "POST", "/api/analytics/batch", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let ids: Vec<&String> = batch.as_object().unwrap().keys().collect();
+        assert_eq!(ids, vec!["_x_Y-z", "spend_x"]);
+    }
+
+    // test that a repeat analytics request is answered from the cache, and that a change to the user's data clears it
+    #[tokio::test]
+    async fn test_analytics_cache_hit_and_invalidation() {
+        let mut state = common::setup_app_state().await;
+        state.analytics_cache = financetracker::AnalyticsCache::new(std::time::Duration::from_secs(30));
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let first = common::insert_test_transaction(&state, user_id, "90.00", "expense", Some("Rent"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Food"), "2026-01-06", None).await;
+
+        let (status, largest) = common::send_request(&app, "GET", "/api/analytics/largest", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(largest.as_array().unwrap().len(), 2);
+        let misses = state.analytics_cache.misses();
+
+        // a row written behind the API's back doesn't show up until the cache is cleared, which proves the hit
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", Some("Food"), "2026-01-07", None).await;
+        let (_, cached) = common::send_request(&app, "GET", "/api/analytics/largest", Some(&access_token), None).await;
+        assert_eq!(cached, largest);
+        assert_eq!(state.analytics_cache.misses(), misses);
+
+        // deleting through the API clears the user's cached analytics
+        let body = serde_json::json!({ "ids": [first], "confirm": true });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let (_, fresh) = common::send_request(&app, "GET", "/api/analytics/largest", Some(&access_token), None).await;
+        assert_eq!(state.analytics_cache.misses(), misses + 1);
+        let amounts: Vec<&str> = fresh.as_array().unwrap().iter().map(|t| t["amount"].as_str().unwrap()).collect();
+        assert_eq!(amounts, vec!["40.00", "10.00"]);
+    }
+
+    // test that a batch returns every analytic it asked for, with a bad one failing on its own
+    #[tokio::test]
+    async fn test_analytics_batch() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "2000.00", "income", Some("Salary"), "2026-01-01", None).await;
+        common::insert_test_transaction(&state, user_id, "90.00", "expense", Some("Rent"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Food"), "2026-02-06", None).await;
+
+        let body = serde_json::json!({ "requests": [
+            { "id": "income", "analytic": "income-by-source" },
+            { "id": "top", "analytic": "largest", "params": { "limit": 1 } },
+            { "id": "monthly", "analytic": "cashflow", "params": { "interval": "month", "from": "2026-01-01" } },
+            { "id": "broken", "analytic": "compare", "params": { "period_a_from": "2026-01-01" } },
+        ]});
+        let (status, results) = common::send_request(&app, "POST", "/api/analytics/batch", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        assert_eq!(results["income"]["status"], 200);
+        assert_eq!(results["income"]["data"][0]["total"], "2000.00");
+        assert_eq!(results["top"]["status"], 200);
+        assert_eq!(results["top"]["data"].as_array().unwrap().len(), 1);
+        assert_eq!(results["top"]["data"][0]["amount"], "90.00");
+        assert_eq!(results["monthly"]["status"], 200);
+        assert_eq!(results["monthly"]["data"].as_array().unwrap().len(), 2);
+        assert_eq!(results["monthly"]["data"][1]["cumulative"], "1870.00");
+
+        assert_eq!(results["broken"]["status"], 400);
+        assert_eq!(results["broken"]["data"], serde_json::Value::Null);
+
+        // too many at once is rejected as a whole
+        let requests: Vec<serde_json::Value> = (0..11).map(|i| serde_json::json!({ "id": i.to_string(), "analytic": "cashflow" })).collect();
+        let (status, _) = common::send_request(&app, "POST", "/api/analytics/batch", Some(&access_token), Some(serde_json::json!({ "requests": requests }))).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,655 @@
+mod common;
+
+use financetracker::build_app;
+
+// use the test module
+#[cfg(test)]
+mod budget_tests {
+    use super::*;
+
+    // test that empty and whitespace-only budget categories are rejected
+    #[tokio::test]
+    async fn test_budget_rejects_blank_category() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for category in ["", "   "] {
+            let body = serde_json::json!({ "month": "2026-01-01", "category": category, "amount": "100.00" });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        // nothing was stored
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets", Some(&access_token), None).await;
+        assert_eq!(budgets, serde_json::json!([]));
+    }
+
+    // test that budget categories are trimmed before they're stored
+    #[tokio::test]
+    async fn test_budget_category_is_trimmed() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "  Food ", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets", Some(&access_token), None).await;
+        assert_eq!(budgets[0]["category"], "Food");
+    }
+
+    // test that the per-month category cap rejects a new category but still allows updating existing ones
+    #[tokio::test]
+    async fn test_budget_category_cap() {
+        let mut state = common::setup_app_state().await;
+        state.config.max_budget_categories_per_month = 2;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for category in ["Food", "Gym"] {
+            let body = serde_json::json!({ "month": "2026-01-01", "category": category, "amount": "100.00" });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "Travel", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // an existing category can still be changed, and other months have their own room
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "Food", "amount": "150.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        let body = serde_json::json!({ "month": "2026-02-01", "category": "Travel", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets?month=2026-01-01", Some(&access_token), None).await;
+        assert_eq!(budgets.as_array().unwrap().len(), 2);
+        assert_eq!(budgets[0]["amount"], "150.00");
+    }
+
+    // test that copying or averaging budgets can't add categories past the cap either, and writes nothing when it would
+    #[tokio::test]
+    async fn test_budget_category_cap_on_bulk_writes() {
+        let mut state = common::setup_app_state().await;
+        state.config.max_budget_categories_per_month = 2;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for category in ["Food", "Gym"] {
+            let body = serde_json::json!({ "month": "2026-01-01", "category": category, "amount": "100.00" });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        // February already has one category of its own, so copying both of January's would make three
+        let body = serde_json::json!({ "month": "2026-02-01", "category": "Travel", "amount": "100.00" });
+        common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        let body = serde_json::json!({ "month": "2026-02-01" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets/copy", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets?month=2026-02-01", Some(&access_token), None).await;
+        assert_eq!(budgets.as_array().unwrap().len(), 1);
+
+        // three categories of spend in the window would need three budgets in April
+        for category in ["Food", "Gym", "Travel"] {
+            common::insert_test_transaction(&state, user_id, "30.00", "expense", Some(category), "2026-03-05", None).await;
+        }
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets/from-averages?months=1&target_month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets?month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(budgets.as_array().unwrap().len(), 0);
+    }
+
+    // test copying last month's budgets, where a second run skips everything and nothing is overwritten
+    #[tokio::test]
+    async fn test_copy_budgets_is_idempotent() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for (category, amount) in [("Food", "300.00"), ("Gym", "50.00")] {
+            let body = serde_json::json!({ "month": "2026-01-01", "category": category, "amount": amount });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        // February already has its own Food budget
+        let body = serde_json::json!({ "month": "2026-02-01", "category": "Food", "amount": "250.00" });
+        common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+
+        let body = serde_json::json!({ "month": "2026-02-01" });
+        let (status, result) = common::send_request(&app, "POST", "/api/budgets/copy", Some(&access_token), Some(body.clone())).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(result, serde_json::json!({ "copied": 1, "skipped_existing": 1 }));
+
+        let (status, result) = common::send_request(&app, "POST", "/api/budgets/copy", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(result, serde_json::json!({ "copied": 0, "skipped_existing": 2 }));
+
+        // February's own Food budget was kept
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets?month=2026-02-01", Some(&access_token), None).await;
+        let food = budgets.as_array().unwrap().iter().find(|b| b["category"] == "Food").unwrap();
+        assert_eq!(food["amount"], "250.00");
+    }
+
+    // test that an expense flagged exclude_from_budget stays on record but doesn't count toward budget progress
+    #[tokio::test]
+    async fn test_excluded_expense_skipped_in_budget_progress() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "month": "2026-04-01", "category": "Travel", "amount": "500.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        common::insert_test_transaction(&state, user_id, "120.00", "expense", Some("Travel"), "2026-04-03", Some("weekend away")).await;
+        let reimbursed = common::insert_test_transaction(&state, user_id, "900.00", "expense", Some("Travel"), "2026-04-10", Some("work trip")).await;
+
+        sqlx::query("UPDATE transactions SET exclude_from_budget = true WHERE id = $1")
+            .bind(reimbursed)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        let (status, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(progress[0]["category"], "Travel");
+        assert_eq!(progress[0]["spent"], "120.00");
+
+        // the excluded expense is still listed, with its flag
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        let work_trip = transactions.as_array().unwrap().iter().find(|t| t["description"] == "work trip").unwrap();
+        assert_eq!(work_trip["exclude_from_budget"], true);
+    }
+
+    // test filtering budget progress by a category prefix (case-insensitive, with LIKE wildcards taken literally)
+    #[tokio::test]
+    async fn test_budget_progress_category_filter() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for category in ["Food", "Fuel", "Gym"] {
+            let body = serde_json::json!({ "month": "2026-05-01", "category": category, "amount": "100.00" });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+        common::insert_test_transaction(&state, user_id, "42.00", "expense", Some("Food"), "2026-05-02", None).await;
+
+        let (status, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-05-01&category=food", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(progress.as_array().unwrap().len(), 1);
+        assert_eq!(progress[0]["category"], "Food");
+        assert_eq!(progress[0]["spent"], "42.00");
+
+        // a prefix matches every category starting with it
+        let (_, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-05-01&category=F", Some(&access_token), None).await;
+        let categories: Vec<&str> = progress.as_array().unwrap().iter().map(|p| p["category"].as_str().unwrap()).collect();
+        assert_eq!(categories, vec!["Food", "Fuel"]);
+
+        // '_' isn't a wildcard
+        let (_, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-05-01&category=_ym", Some(&access_token), None).await;
+        assert_eq!(progress, serde_json::json!([]));
+
+        // without the filter, every category comes back
+        let (_, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-05-01", Some(&access_token), None).await;
+        assert_eq!(progress.as_array().unwrap().len(), 3);
+    }
+
+    // test that the pace warning fires once more than the pro-rata share of a budget is spent, and not before
+    #[tokio::test]
+    async fn test_budget_progress_pace_warning() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // budgets of 10 a day make the pro-rata share a whole number: 10 for each day elapsed (today included)
+        let today = chrono::Utc::now().date_naive();
+        let this_month = chrono::Datelike::with_day(&today, 1).unwrap();
+        let days_in_month = ((this_month + chrono::Months::new(1)) - this_month).num_days();
+        let days_elapsed = chrono::Datelike::day(&today) as i64;
+        let budget = format!("{}.00", days_in_month * 10);
+
+        for category in ["Food", "Gym"] {
+            let body = serde_json::json!({ "month": this_month, "category": category, "amount": budget });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        // Food is just ahead of pace, Gym exactly on it
+        let ahead = format!("{}.00", days_elapsed * 10 + 1);
+        let on_pace = format!("{}.00", days_elapsed * 10);
+        common::insert_test_transaction(&state, user_id, &ahead, "expense", Some("Food"), &today.to_string(), None).await;
+        common::insert_test_transaction(&state, user_id, &on_pace, "expense", Some("Gym"), &today.to_string(), None).await;
+
+        let (status, progress) = common::send_request(&app, "GET", "/api/budgets/progress", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(progress[0]["category"], "Food");
+        assert_eq!(progress[0]["pace_warning"], true);
+        assert_eq!(progress[1]["category"], "Gym");
+        assert_eq!(progress[1]["pace_warning"], false);
+    }
+
+    // test that budget progress reports a crossed soft limit separately from the hard cap, and that warn_at can't be above cap_at
+    #[tokio::test]
+    async fn test_budget_progress_spending_limits() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let today = chrono::Utc::now().date_naive();
+        let this_month = chrono::Datelike::with_day(&today, 1).unwrap();
+
+        let body = serde_json::json!({ "month": this_month, "category": "Food", "amount": "100.00", "warn_at": "90.00", "cap_at": "80.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let body = serde_json::json!({ "month": this_month, "category": "Food", "amount": "100.00", "warn_at": "80.00", "cap_at": "120.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        // reaching the soft limit is enough to warn
+        common::insert_test_transaction(&state, user_id, "80.00", "expense", Some("Food"), &this_month.to_string(), None).await;
+
+        let (status, progress) = common::send_request(&app, "GET", "/api/budgets/progress", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(progress[0]["limits"], serde_json::json!({ "warn_at": "80.00", "cap_at": "120.00" }));
+        assert_eq!(progress[0]["warn_crossed"], true);
+        assert_eq!(progress[0]["cap_exceeded"], false);
+    }
+
+    // test that with limits enforced, an expense past the overall cap is rejected before it's saved
+    #[tokio::test]
+    async fn test_spending_cap_rejects_expense() {
+        let mut state = common::setup_app_state().await;
+        state.config.enforce_spending_limits = true;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let today = chrono::Utc::now().date_naive();
+        let body = serde_json::json!({ "month": today, "warn_at": "50.00", "cap_at": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets/limits", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let food = common::insert_test_transaction(&state, user_id, "60.00", "expense", Some("Food"), &today.to_string(), None).await;
+
+        let (status, limits) = common::send_request(&app, "GET", "/api/budgets/limits", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(limits["spent"], "60.00");
+        assert_eq!(limits["warn_crossed"], true);
+        assert_eq!(limits["cap_exceeded"], false);
+
+        // 60 + 50 goes past the cap of 100 (checked before anything is embedded or stored)
+        let body = serde_json::json!({ "amount": "50.00", "kind": "Expense", "category": "Gym", "date": today });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+
+        let (_, limits) = common::send_request(&app, "GET", "/api/budgets/limits", Some(&access_token), None).await;
+        assert_eq!(limits["spent"], "60.00");
+
+        // editing an expense is checked too, with its own current amount replaced rather than added
+        let uri = format!("/api/transactions/{}", food);
+        let body = serde_json::json!({ "amount": "120.00", "kind": "Expense", "category": "Food" });
+        let (status, _) = common::send_request(&app, "PUT", &uri, Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+        let body = serde_json::json!({ "amount": "90.00", "kind": "Expense", "category": "Food" });
+        let (status, _) = common::send_request(&app, "PUT", &uri, Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let (_, limits) = common::send_request(&app, "GET", "/api/budgets/limits", Some(&access_token), None).await;
+        assert_eq!(limits["spent"], "90.00");
+    }
+
+    // test that two members of a household see its shared budget spent by both of them, and outsiders can't see it
+    #[tokio::test]
+    async fn test_household_budget_progress() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (first_username, first_password) = common::create_and_register_test_user(&app).await;
+        let (first_id, first_token) = common::login_test_user(&app, &first_username, &first_password).await;
+        let (second_username, second_password) = common::create_and_register_test_user(&app).await;
+        let (second_id, second_token) = common::login_test_user(&app, &second_username, &second_password).await;
+        let (outsider_username, outsider_password) = common::create_and_register_test_user(&app).await;
+        let (_, outsider_token) = common::login_test_user(&app, &outsider_username, &outsider_password).await;
+
+        let body = serde_json::json!({ "name": "Flat 4" });
+        let (status, household) = common::send_request(&app, "POST", "/api/households", Some(&first_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        let household_id = household["id"].as_str().unwrap().to_string();
+
+        let body = serde_json::json!({ "username": second_username });
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/households/{}/members", household_id), Some(&first_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/households/{}/accept", household_id), Some(&second_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        let this_month = chrono::Datelike::with_day(&chrono::Utc::now().date_naive(), 1).unwrap();
+        let body = serde_json::json!({ "month": this_month, "category": "Groceries", "amount": "300.00" });
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/households/{}/budgets", household_id), Some(&second_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        common::insert_test_transaction(&state, first_id, "70.00", "expense", Some("Groceries"), &this_month.to_string(), None).await;
+        common::insert_test_transaction(&state, second_id, "55.50", "expense", Some("Groceries"), &this_month.to_string(), None).await;
+
+        // both members see the combined spend
+        let uri = format!("/api/budgets/progress?household_id={}", household_id);
+        for token in [&first_token, &second_token] {
+            let (status, progress) = common::send_request(&app, "GET", &uri, Some(token), None).await;
+            assert_eq!(status, axum::http::StatusCode::OK);
+            assert_eq!(progress.as_array().unwrap().len(), 1);
+            assert_eq!(progress[0]["category"], "Groceries");
+            assert_eq!(progress[0]["spent"], "125.50");
+            assert_eq!(progress[0]["remaining"], "174.50");
+        }
+
+        // the shared budget isn't one of the members' personal budgets
+        let (_, personal) = common::send_request(&app, "GET", "/api/budgets/progress", Some(&first_token), None).await;
+        assert_eq!(personal, serde_json::json!([]));
+
+        let (_, households) = common::send_request(&app, "GET", "/api/households", Some(&second_token), None).await;
+        assert_eq!(households[0]["name"], "Flat 4");
+
+        let (status, _) = common::send_request(&app, "GET", &uri, Some(&outsider_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+        let body = serde_json::json!({ "username": outsider_username });
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/households/{}/members", household_id), Some(&outsider_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    // test that inviting someone doesn't let the household see their data until they accept, and that declining works
+    #[tokio::test]
+    async fn test_household_invite_needs_acceptance() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (owner_username, owner_password) = common::create_and_register_test_user(&app).await;
+        let (owner_id, owner_token) = common::login_test_user(&app, &owner_username, &owner_password).await;
+        let (invitee_username, invitee_password) = common::create_and_register_test_user(&app).await;
+        let (invitee_id, invitee_token) = common::login_test_user(&app, &invitee_username, &invitee_password).await;
+
+        let body = serde_json::json!({ "name": "Snoops" });
+        let (_, household) = common::send_request(&app, "POST", "/api/households", Some(&owner_token), Some(body)).await;
+        let household_id = household["id"].as_str().unwrap().to_string();
+
+        let body = serde_json::json!({ "username": invitee_username });
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/households/{}/members", household_id), Some(&owner_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        common::insert_test_transaction(&state, owner_id, "10.00", "expense", Some("Food"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, invitee_id, "999.00", "income", None, "2026-01-06", None).await;
+
+        // before accepting, the household only covers the owner's transactions
+        let uri = format!("/api/analytics/cashflow?household_id={}", household_id);
+        let (status, cashflow) = common::send_request(&app, "GET", &uri, Some(&owner_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(cashflow.as_array().unwrap().len(), 1);
+        assert_eq!(cashflow[0]["net"], "-10.00");
+
+        // and the invitee can't use it yet either
+        let (status, _) = common::send_request(&app, "GET", &uri, Some(&invitee_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+
+        // the invitee sees the invite, and declining it leaves them out for good
+        let (_, invites) = common::send_request(&app, "GET", "/api/households/invites", Some(&invitee_token), None).await;
+        assert_eq!(invites.as_array().unwrap().len(), 1);
+        assert_eq!(invites[0]["household_name"], "Snoops");
+        assert_eq!(invites[0]["invited_by"], owner_username);
+
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/households/{}/decline", household_id), Some(&invitee_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/households/{}/accept", household_id), Some(&invitee_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+
+        let (_, cashflow) = common::send_request(&app, "GET", &uri, Some(&owner_token), None).await;
+        assert_eq!(cashflow[0]["net"], "-10.00");
+        let (_, invites) = common::send_request(&app, "GET", "/api/households/invites", Some(&invitee_token), None).await;
+        assert_eq!(invites, serde_json::json!([]));
+    }
+
+    // test that only overspent budgets are listed as overruns, the biggest overage first
+    #[tokio::test]
+    async fn test_budget_overruns() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for (category, amount) in [("Food", "100.00"), ("Fun", "50.00"), ("Gym", "40.00"), ("Travel", "200.00")] {
+            let body = serde_json::json!({ "month": "2026-01-01", "category": category, "amount": amount });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        // Food and Fun are over, Gym is exactly on budget and Travel under
+        common::insert_test_transaction(&state, user_id, "110.00", "expense", Some("Food"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "75.50", "expense", Some("Fun"), "2026-01-06", None).await;
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Gym"), "2026-01-07", None).await;
+        common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Travel"), "2026-01-08", None).await;
+
+        let (status, overruns) = common::send_request(&app, "GET", "/api/budgets/overruns?month=2026-01-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(overruns, serde_json::json!([
+            { "category": "Fun", "budget": "50.00", "spent": "75.50", "overage": "25.50" },
+            { "category": "Food", "budget": "100.00", "spent": "110.00", "overage": "10.00" },
+        ]));
+    }
+
+    // test that budget adherence is the sum of the month's progress rows, with a clear status when nothing is budgeted
+    #[tokio::test]
+    async fn test_budget_adherence() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // nothing budgeted yet
+        let (status, adherence) = common::send_request(&app, "GET", "/api/budgets/adherence?month=2026-01-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(adherence["status"], "no_budget");
+        assert_eq!(adherence["percent_spent"], serde_json::Value::Null);
+
+        for (category, amount) in [("Food", "100.00"), ("Gym", "40.00")] {
+            let body = serde_json::json!({ "month": "2026-01-01", "category": category, "amount": amount });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        // Food is over and Gym under, but together they're within budget; unbudgeted Travel doesn't count
+        common::insert_test_transaction(&state, user_id, "110.00", "expense", Some("Food"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Gym"), "2026-01-07", None).await;
+        common::insert_test_transaction(&state, user_id, "500.00", "expense", Some("Travel"), "2026-01-08", None).await;
+
+        let (_, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-01-01", Some(&access_token), None).await;
+        let sum = |field: &str| -> rust_decimal::Decimal {
+            progress.as_array().unwrap().iter().map(|row| row[field].as_str().unwrap().parse::<rust_decimal::Decimal>().unwrap()).sum()
+        };
+
+        let (status, adherence) = common::send_request(&app, "GET", "/api/budgets/adherence?month=2026-01-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(adherence["budgeted"].as_str().unwrap().parse::<rust_decimal::Decimal>().unwrap(), sum("budget_amount"));
+        assert_eq!(adherence["spent"].as_str().unwrap().parse::<rust_decimal::Decimal>().unwrap(), sum("spent"));
+        assert_eq!(adherence["budgeted"], "140.00");
+        assert_eq!(adherence["spent"], "130.00");
+        assert_eq!(adherence["percent_spent"], "92.9");
+        assert_eq!(adherence["status"], "on_track");
+    }
+
+    // test one category's budget history over the last few months, including a month with no budget
+    #[tokio::test]
+    async fn test_budget_progress_history() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // the history ends at the current month, so build the dates from today
+        let this_month = chrono::Datelike::with_day(&chrono::Utc::now().date_naive(), 1).unwrap();
+        let last_month = this_month - chrono::Months::new(1);
+        let two_months_ago = this_month - chrono::Months::new(2);
+
+        for (month, amount) in [(two_months_ago, "300.00"), (this_month, "250.00")] {
+            let body = serde_json::json!({ "month": month, "category": "Food", "amount": amount });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        common::insert_test_transaction(&state, user_id, "120.00", "expense", Some("Food"), &two_months_ago.to_string(), None).await;
+        common::insert_test_transaction(&state, user_id, "80.00", "expense", Some("Food"), &last_month.to_string(), None).await;
+        common::insert_test_transaction(&state, user_id, "15.00", "expense", Some("Food"), &this_month.to_string(), None).await;
+        common::insert_test_transaction(&state, user_id, "50.00", "expense", Some("Gym"), &this_month.to_string(), None).await;
+
+        let (status, history) = common::send_request(&app, "GET", "/api/budgets/progress/history?category=Food&months=3", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(history, serde_json::json!([
+            { "month": two_months_ago, "budgeted": "300.00", "spent": "120.00" },
+            { "month": last_month, "budgeted": null, "spent": "80.00" },
+            { "month": this_month, "budgeted": "250.00", "spent": "15.00" },
+        ]));
+
+        // six months by default
+        let (_, history) = common::send_request(&app, "GET", "/api/budgets/progress/history?category=Food", Some(&access_token), None).await;
+        assert_eq!(history.as_array().unwrap().len(), 6);
+    }
+
+    // test that budgets generated from trailing averages match each category's average monthly spend
+    #[tokio::test]
+    async fn test_budgets_from_averages() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // an existing budget in the target month gets replaced by the average
+        let body = serde_json::json!({ "month": "2026-04-01", "category": "Food", "amount": "999.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        // January to March is the window; a quiet month still counts towards the average
+        common::insert_test_transaction(&state, user_id, "100.00", "expense", Some("Food"), "2026-01-10", None).await;
+        common::insert_test_transaction(&state, user_id, "50.00", "expense", Some("Food"), "2026-03-05", None).await;
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", Some("Gym"), "2026-02-01", None).await;
+        // outside the window, or not spending
+        common::insert_test_transaction(&state, user_id, "500.00", "expense", Some("Food"), "2025-12-31", None).await;
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Travel"), "2026-04-02", None).await;
+        common::insert_test_transaction(&state, user_id, "2000.00", "income", Some("Salary"), "2026-02-15", None).await;
+
+        let (status, budgets) = common::send_request(&app, "POST", "/api/budgets/from-averages?months=3&target_month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        let generated: Vec<(&str, &str)> = budgets.as_array().unwrap().iter()
+            .map(|b| (b["category"].as_str().unwrap(), b["amount"].as_str().unwrap()))
+            .collect();
+        assert_eq!(generated, vec![("Food", "50.00"), ("Gym", "3.33")]);
+
+        let (_, stored) = common::send_request(&app, "GET", "/api/budgets?month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(stored.as_array().unwrap().len(), 2);
+        assert_eq!(stored[0]["amount"], "50.00");
+    }
+
+    // test that a suggested budget averages the category's own spend, and falls back to the other categories' average without any
+    #[tokio::test]
+    async fn test_suggest_budget() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // January to March is the window for April
+        common::insert_test_transaction(&state, user_id, "30.00", "expense", Some("Food"), "2026-01-10", None).await;
+        common::insert_test_transaction(&state, user_id, "60.00", "expense", Some("Food"), "2026-03-05", None).await;
+        common::insert_test_transaction(&state, user_id, "15.00", "expense", Some("Gym"), "2026-02-01", None).await;
+        common::insert_test_transaction(&state, user_id, "400.00", "expense", Some("Food"), "2026-04-02", None).await;
+
+        let (status, suggestion) = common::send_request(&app, "GET", "/api/budgets/suggest?category=Food&month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(suggestion["suggested_amount"], "30.00");
+        assert_eq!(suggestion["basis"], "history");
+        assert_eq!(suggestion["sample_months"], 2);
+
+        // (90 + 15) over two categories and three months
+        let (status, suggestion) = common::send_request(&app, "GET", "/api/budgets/suggest?category=Pets&month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(suggestion["suggested_amount"], "17.50");
+        assert_eq!(suggestion["basis"], "default");
+        assert_eq!(suggestion["sample_months"], 0);
+    }
+
+    // test the category overview lists budget-only, spend-only and budgeted-and-spent categories together
+    #[tokio::test]
+    async fn test_category_overview() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for (category, amount) in [("Food", "300.00"), ("Gym", "50.00")] {
+            let body = serde_json::json!({ "month": "2026-04-01", "category": category, "amount": amount });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        // food has a budget and spend, travel only spend, gym only a budget, and last month's books only old spend
+        common::insert_test_transaction(&state, user_id, "120.00", "expense", Some("Food"), "2026-04-03", None).await;
+        common::insert_test_transaction(&state, user_id, "45.50", "expense", Some("Travel"), "2026-04-20", None).await;
+        common::insert_test_transaction(&state, user_id, "30.00", "expense", Some("Books"), "2026-03-15", None).await;
+        common::insert_test_transaction(&state, user_id, "9.00", "expense", None, "2026-04-05", None).await;
+
+        let (status, overview) = common::send_request(&app, "GET", "/api/categories/overview?month=2026-04-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(overview, serde_json::json!([
+            { "category": "Books", "budget": null, "spent": "0" },
+            { "category": "Food", "budget": "300.00", "spent": "120.00" },
+            { "category": "Gym", "budget": "50.00", "spent": "0" },
+            { "category": "Travel", "budget": null, "spent": "45.50" },
+        ]));
+    }
+
+    // test that past-month budgets can be edited by default
+    #[tokio::test]
+    async fn test_past_budget_edits_allowed_by_default() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "month": "2020-01-01", "category": "Food", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+    }
+
+    // test that turning past budget edits off rejects a past month but still allows later ones
+    #[tokio::test]
+    async fn test_past_budget_edits_rejected_when_disabled() {
+        let mut state = common::setup_app_state().await;
+        state.config.allow_past_budget_edits = false;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "month": "2020-01-01", "category": "Food", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+
+        let body = serde_json::json!({ "month": "2099-01-01", "category": "Food", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        // only the later budget was stored
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets", Some(&access_token), None).await;
+        assert_eq!(budgets.as_array().unwrap().len(), 1);
+        assert_eq!(budgets[0]["month"], "2099-01-01");
+    }
+}
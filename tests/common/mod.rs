@@ -1,3 +1,6 @@
+// not every test binary uses every helper in here
+#![allow(dead_code)]
+
 use financetracker::AppState;
 use http_body_util::BodyExt;
 use tower::util::ServiceExt;
@@ -67,6 +70,22 @@ pub async fn login_test_user(app: &axum::Router, username: &str, password: &str)
     (response.user_id, response.access_token)
 }
 
+// helper function to bring the test database's schema up to date, so a fresh CI database works without a manual
+// migration step. every test calls it, but the migrations only run once per test binary (sqlx also takes an
+// advisory lock while migrating and skips anything already applied, so separate binaries racing is fine too)
+pub async fn setup_test_db(pool: &sqlx::PgPool) {
+    static MIGRATED: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+
+    MIGRATED
+        .get_or_init(|| async {
+            sqlx::migrate!("./backend/migrations")
+                .run(pool)
+                .await
+                .expect("Could not run migrations against the test database");
+        })
+        .await;
+}
+
 // helper function to set up app state
 pub async fn setup_app_state() -> AppState {
     // load .env variables from backend/.env (the working directory during tests is the workspace root)
@@ -83,10 +102,102 @@ pub async fn setup_app_state() -> AppState {
         .await
         .unwrap();
 
+    setup_test_db(&pool).await;
+
     AppState {
         pool,
         jwt_secret: jwt_secret.clone(),
         openai_api_key: openai_api_key.clone(),
         http_client,
+        config: financetracker::Config::default(),
+        registration_limiter: financetracker::RateLimiter::for_registration(&financetracker::Config::default()),
+        analytics_cache: financetracker::AnalyticsCache::for_analytics(&financetracker::Config::default()),
+    }
+}
+
+// helper function to insert a transaction directly into the database and return its id
+// (this skips the add_transaction handler so tests don't need to call out to OpenAI for an embedding)
+pub async fn insert_test_transaction(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    amount: &str,
+    kind: &str,
+    category: Option<&str>,
+    date: &str,
+    description: Option<&str>,
+) -> uuid::Uuid {
+    let amount: rust_decimal::Decimal = amount.parse().unwrap();
+    let date: chrono::NaiveDate = date.parse().unwrap();
+
+    sqlx::query_scalar(
+        "INSERT INTO transactions (user_id, amount, kind, category, date, description)
+        VALUES ($1, $2, $3, $4, $5, $6) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(amount)
+    .bind(kind)
+    .bind(category)
+    .bind(date)
+    .bind(description)
+    .fetch_one(&state.pool)
+    .await
+    .unwrap()
+}
+
+// helper function to send a request (optionally authenticated, optionally with a JSON body) and
+// return the status code along with the parsed JSON body (Null if the body is empty or not JSON)
+pub async fn send_request(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    access_token: Option<&str>,
+    body: Option<serde_json::Value>,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    let mut builder = axum::http::Request::builder()
+        .method(method)
+        .uri(uri);
+
+    if let Some(token) = access_token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
     }
+
+    let request = match body {
+        Some(json) => builder
+            .header("Content-Type", "application/json")
+            .body(axum::body::Body::from(json.to_string()))
+            .unwrap(),
+        None => builder.body(axum::body::Body::empty()).unwrap(),
+    };
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let json = serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null);
+
+    (status, json)
+}
+
+// helper function to send an authenticated GET request and return the status, headers and raw text body
+// (for non-JSON responses like CSV exports)
+pub async fn send_raw_request(
+    app: &axum::Router,
+    uri: &str,
+    access_token: &str,
+) -> (axum::http::StatusCode, axum::http::HeaderMap, String) {
+    let request = axum::http::Request::builder()
+        .method("GET")
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+
+    (status, headers, body)
 }
@@ -0,0 +1,133 @@
+mod common;
+
+use financetracker::build_app;
+
+// use the test module
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+
+    // test exporting a few hundred transactions as CSV, honoring the date and category filters
+    #[tokio::test]
+    async fn test_transactions_csv_export() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // seed 300 grocery expenses across 2025 in one go
+        sqlx::query(
+            "INSERT INTO transactions (user_id, amount, kind, category, date, description)
+            SELECT $1, n, 'expense', 'Groceries', DATE '2025-01-01' + n, 'Trip ' || n
+            FROM generate_series(1, 300) AS n"
+        )
+        .bind(user_id)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        // plus a couple that the filters should leave out
+        common::insert_test_transaction(&state, user_id, "99.00", "expense", Some("Rent"), "2025-03-01", Some("Rent, March")).await;
+        common::insert_test_transaction(&state, user_id, "5.00", "expense", Some("Groceries"), "2026-01-05", None).await;
+
+        let (status, headers, body) = common::send_raw_request(&app, "/api/transactions/export.csv?from=2025-01-01&to=2025-12-31&category=Groceries", &access_token).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(headers["content-type"], "text/csv; charset=utf-8");
+
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines[0], "id,date,kind,amount,category,description,source,status");
+        assert_eq!(lines.len(), 301);
+
+        // rows come out in date order
+        let first: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(&first[1..], &["2025-01-02", "expense", "1.00", "Groceries", "Trip 1", "", "cleared"]);
+        let last: Vec<&str> = lines[300].split(',').collect();
+        assert_eq!(&last[1..], &["2025-10-28", "expense", "300.00", "Groceries", "Trip 300", "", "cleared"]);
+
+        // fields with commas get quoted
+        let (_, _, rent) = common::send_raw_request(&app, "/api/transactions/export.csv?category=Rent", &access_token).await;
+        assert!(rent.lines().nth(1).unwrap().ends_with(",2025-03-01,expense,99.00,Rent,\"Rent, March\",,cleared"));
+    }
+
+    // test exporting budgets as CSV, limited to a range of months
+    #[tokio::test]
+    async fn test_budgets_csv_export() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let budgets = [
+            ("2026-01-01", "Food", "300.00"),
+            ("2026-02-01", "Rent, shared", "800.00"),
+            ("2026-02-01", "Food", "250.00"),
+            ("2026-03-01", "Food", "275.00"),
+        ];
+        for (month, category, amount) in budgets {
+            let body = serde_json::json!({ "month": month, "category": category, "amount": amount });
+            let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        let (status, headers, body) = common::send_raw_request(&app, "/api/budgets/export.csv?from=2026-02-01&to=2026-03-15", &access_token).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(headers["content-type"], "text/csv; charset=utf-8");
+
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines, vec![
+            "month,category,amount",
+            "2026-02-01,Food,250.00",
+            "2026-02-01,\"Rent, shared\",800.00",
+            "2026-03-01,Food,275.00",
+        ]);
+    }
+
+    // test exporting a month's report as JSON and as a multi-section CSV
+    #[tokio::test]
+    async fn test_monthly_report_export() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "month": "2026-03-01", "category": "Food", "amount": "300.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Food"), "2026-03-04", Some("Market")).await;
+        common::insert_test_transaction(&state, user_id, "2000.00", "income", Some("Salary"), "2026-03-25", None).await;
+        // another month, left out
+        common::insert_test_transaction(&state, user_id, "15.00", "expense", Some("Food"), "2026-04-01", None).await;
+
+        let (status, headers, body) = common::send_raw_request(&app, "/api/reports/monthly/2026-03-15/export?format=json", &access_token).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(headers["content-disposition"], "attachment; filename=\"report-2026-03.json\"");
+
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["month"], "2026-03-01");
+        assert_eq!(report["transactions"].as_array().unwrap().len(), 2);
+        assert_eq!(report["transactions"][0]["description"], "Market");
+        assert_eq!(report["budgets"][0]["amount"], "300.00");
+        assert_eq!(report["budget_progress"][0]["spent"], "40.00");
+
+        let (status, headers, body) = common::send_raw_request(&app, "/api/reports/monthly/2026-03-01/export?format=csv", &access_token).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(headers["content-type"], "text/csv; charset=utf-8");
+
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines[0], "# transactions");
+        assert_eq!(lines[1], "id,date,kind,amount,category,description,source,status");
+        assert!(lines[2].ends_with(",2026-03-04,expense,40.00,Food,Market,,cleared"));
+        assert!(lines[3].ends_with(",2026-03-25,income,2000.00,Salary,,,cleared"));
+        assert_eq!(&lines[4..], &[
+            "",
+            "# budgets",
+            "month,category,amount",
+            "2026-03-01,Food,300.00",
+            "",
+            "# budget progress",
+            "category,budget_amount,spent,remaining",
+            "Food,300.00,40.00,260.00",
+        ]);
+    }
+}
@@ -12,7 +12,7 @@ struct LoginResponse {
     access_token: String,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct Claims {
     sub: String,
     exp: usize,
@@ -185,5 +185,159 @@ mod jwt_tests {
         assert_eq!(response.status(), axum::http::StatusCode::OK);
     }
 
-}
 
+    // helper function to mint a token for a user, signed with the given secret and carrying the given kid
+    fn mint_token(user_id: uuid::Uuid, secret: &str, kid: Option<&str>) -> String {
+        let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256);
+        header.kid = kid.map(|k| k.to_string());
+
+        let exp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as usize + 3600;
+
+        let claims = Claims { sub: user_id.to_string(), exp };
+
+        jsonwebtoken::encode(&header, &claims, &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes())).unwrap()
+    }
+
+    // check that issued tokens carry typ and kid, and that kid selects the right key during a rotation
+    #[tokio::test]
+    async fn test_jwt_kid_selects_matching_key() {
+        // set up app state with a current key id and one older key still accepted
+        let mut state = common::setup_app_state().await;
+        state.config.jwt_key_id = Some("current".to_string());
+        state.config.jwt_previous_secrets.insert("previous".to_string(), "previous-secret".to_string());
+
+        let app = build_app(state.clone());
+
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // the issued token should say what it is and which key signed it
+        let header = jsonwebtoken::decode_header(&access_token).unwrap();
+        assert_eq!(header.typ.as_deref(), Some("JWT"));
+        assert_eq!(header.kid.as_deref(), Some("current"));
+
+        // a token signed with the previous key and carrying its kid still verifies
+        let previous_token = mint_token(user_id, "previous-secret", Some("previous"));
+        let (status, _) = common::send_request(&app, "GET", "/api/budgets", Some(&previous_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        // but the same kid with a different signature doesn't
+        let forged_token = mint_token(user_id, &state.jwt_secret, Some("previous"));
+        let (status, _) = common::send_request(&app, "GET", "/api/budgets", Some(&forged_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+
+        // and an unknown kid is rejected outright
+        let unknown_token = mint_token(user_id, "previous-secret", Some("unknown"));
+        let (status, _) = common::send_request(&app, "GET", "/api/budgets", Some(&unknown_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    // check that the validate endpoint accepts a live token and turns away an expired one
+    #[tokio::test]
+    async fn test_validate_token() {
+        let state = common::setup_app_state().await;
+        let jwt_secret = state.jwt_secret.clone();
+        let app = build_app(state);
+
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let (status, validation) = common::send_request(&app, "GET", "/api/auth/validate", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let verified = financetracker::verify_jwt(&access_token, &jwt_secret).unwrap();
+        assert_eq!(validation["valid"], true);
+        assert_eq!(validation["user_id"], user_id.to_string());
+        assert_eq!(validation["expires_at"], verified.expires_at);
+
+        // a token that expired an hour ago (well past the validation leeway)
+        let exp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as usize - 3600;
+        let claims = Claims { sub: user_id.to_string(), exp };
+        let expired_token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(jwt_secret.as_bytes()),
+        ).unwrap();
+
+        let (status, _) = common::send_request(&app, "GET", "/api/auth/validate", Some(&expired_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    // check that the session endpoint reports the same issue and expiry times as the token itself
+    #[tokio::test]
+    async fn test_session_info_matches_token() {
+        let state = common::setup_app_state().await;
+        let jwt_secret = state.jwt_secret.clone();
+        let app = build_app(state);
+
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let (status, session) = common::send_request(&app, "GET", "/api/users/session", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let verified = financetracker::verify_jwt(&access_token, &jwt_secret).unwrap();
+        assert_eq!(session["user_id"], user_id.to_string());
+        assert_eq!(session["expires_at"], verified.expires_at);
+        assert_eq!(session["issued_at"], verified.issued_at.unwrap());
+
+        // a fresh token has (just under) the full lifetime left
+        let expires_in = session["expires_in"].as_u64().unwrap();
+        assert!(expires_in > 23 * 3600 && expires_in <= 24 * 3600);
+
+        // tokens without iat (like ones issued before it was added) still work, just without an issue time
+        let old_token = mint_token(user_id, &jwt_secret, None);
+        let (status, session) = common::send_request(&app, "GET", "/api/users/session", Some(&old_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert!(session["issued_at"].is_null());
+    }
+
+    // check that a token close to expiry can be renewed for one with a later exp, but a fresh one can't
+    #[tokio::test]
+    async fn test_token_renewal() {
+        let state = common::setup_app_state().await;
+        let jwt_secret = state.jwt_secret.clone();
+        let app = build_app(state);
+
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // a just-issued token has its whole life left, so it's outside the renewal window
+        let (status, _) = common::send_request(&app, "POST", "/api/users/token/renew", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // a token with an hour left is inside it
+        let expiring_token = mint_token(user_id, &jwt_secret, None);
+        let old_exp = financetracker::verify_jwt(&expiring_token, &jwt_secret).unwrap().expires_at;
+
+        let (status, renewed) = common::send_request(&app, "POST", "/api/users/token/renew", Some(&expiring_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(renewed["user_id"], user_id.to_string());
+
+        let renewed_token = renewed["access_token"].as_str().unwrap();
+        let renewed = financetracker::verify_jwt(renewed_token, &jwt_secret).unwrap();
+        assert_eq!(renewed.user_id, user_id);
+        assert!(renewed.expires_at > old_exp);
+    }
+
+    // check that an absurd configured token lifetime is clamped to the hard maximum when logging in
+    #[tokio::test]
+    async fn test_jwt_expiration_is_clamped() {
+        let mut state = common::setup_app_state().await;
+        state.config.jwt_expiration_hours = 1_000_000;
+        let jwt_secret = state.jwt_secret.clone();
+        let app = build_app(state);
+
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let verified = financetracker::verify_jwt(&access_token, &jwt_secret).unwrap();
+        let lifetime = verified.expires_at - verified.issued_at.unwrap();
+        assert_eq!(lifetime as u64, financetracker::config::MAX_JWT_EXPIRATION_HOURS * 3600);
+    }
+}
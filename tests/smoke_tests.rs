@@ -10,6 +10,24 @@ mod tests {
     use financetracker::{AppState, build_app};
     use tower::util::ServiceExt; // for oneshot
     
+    // helper function to build app state around a lazy pool, for tests that never (or only fail to) reach the database
+    fn lazy_state(config: financetracker::Config) -> AppState {
+        let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .connect_lazy(&db_url)
+            .unwrap();
+
+        AppState {
+            pool,
+            jwt_secret: "test_secret".to_string(),
+            openai_api_key: "test_openai_key".to_string(),
+            http_client: reqwest::Client::new(),
+            registration_limiter: financetracker::RateLimiter::for_registration(&config),
+            analytics_cache: financetracker::AnalyticsCache::for_analytics(&config),
+            config,
+        }
+    }
 
     // smoke test
     #[tokio::test]
@@ -37,6 +55,9 @@ mod tests {
             jwt_secret: "test_secret".to_string(),
             openai_api_key: "test_openai_key".to_string(),
             http_client: reqwest::Client::new(),
+            config: financetracker::Config::default(),
+            registration_limiter: financetracker::RateLimiter::for_registration(&financetracker::Config::default()),
+            analytics_cache: financetracker::AnalyticsCache::for_analytics(&financetracker::Config::default()),
         };
 
         // build the app router with the state
@@ -64,6 +85,281 @@ mod tests {
 
     }
 
+    // check that switching a feature off drops its routes (404) while the rest of the API keeps working
+    #[tokio::test]
+    async fn test_disabled_feature_routes_are_not_found() {
+        // turn the dev routes off, leaving everything else on (neither route below touches the database)
+        let mut config = financetracker::Config::default();
+        config.features.dev_routes = false;
+
+        let state = lazy_state(config);
+
+        // just the api router, so an unknown path isn't answered by the frontend fallback
+        let app = financetracker::api_router(state);
+
+        // the disabled /api/test route isn't there at all
+        let request = axum::http::Request::builder()
+            .method("GET")
+            .uri("/api/test")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        // while an enabled route still answers (401 without a token, rather than 404)
+        let request = axum::http::Request::builder()
+            .method("GET")
+            .uri("/api/transactions/export.csv")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    // check that posting form-encoded data to a JSON endpoint gets a clear 415 instead of a generic error
+    #[tokio::test]
+    async fn test_json_endpoint_rejects_wrong_content_type() {
+        // the request is rejected before any database access, so a lazy pool is enough
+        let state = lazy_state(financetracker::Config::default());
+
+        let app = build_app(state);
+
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/users/register")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(axum::body::Body::from("username=someone&email=someone%40example.com&password=pw"))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let body = response.into_body().collect().await.unwrap();
+        let body_bytes = body.to_bytes();
+        let body_str = std::str::from_utf8(&body_bytes).unwrap();
+
+        assert_eq!(body_str, "Expected request with Content-Type: application/json");
+    }
+
+    // check that the API router on its own 404s unknown API routes instead of falling through to the SPA
+    #[tokio::test]
+    async fn test_api_router_unknown_route_is_404() {
+        let state = lazy_state(financetracker::Config::default());
+
+        // build just the API routes, without the static frontend fallback
+        let app = financetracker::api_router(state);
+
+        let request = axum::http::Request::builder()
+            .method("GET")
+            .uri("/api/nonexistent")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    // check that credentialed CORS echoes back allowlisted origins (never a wildcard) and ignores others
+    #[tokio::test]
+    async fn test_credentialed_cors_preflight() {
+        let config = financetracker::Config {
+            cors_allowed_origins: vec!["https://app.example.com".to_string()],
+            cors_allow_credentials: true,
+            ..financetracker::Config::default()
+        };
+
+        let state = lazy_state(config);
+
+        let app = financetracker::api_router(state);
+
+        let preflight = |origin: &str| {
+            axum::http::Request::builder()
+                .method("OPTIONS")
+                .uri("/api/transactions")
+                .header("Origin", origin)
+                .header("Access-Control-Request-Method", "POST")
+                .header("Access-Control-Request-Headers", "authorization,content-type")
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(preflight("https://app.example.com")).await.unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["access-control-allow-origin"], "https://app.example.com");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["access-control-allow-methods"], "POST");
+
+        let response = app.oneshot(preflight("https://evil.example.com")).await.unwrap();
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+    }
+
+    // check that fingerprinted assets are cached long-term while index.html (and SPA routes) are always revalidated
+    #[tokio::test]
+    async fn test_frontend_cache_headers() {
+        // a throwaway dist directory laid out like a vite build
+        let dist_dir = std::env::temp_dir().join(format!("financetracker_dist_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dist_dir.join("assets")).unwrap();
+        std::fs::write(dist_dir.join("index.html"), "<!doctype html>").unwrap();
+        std::fs::write(dist_dir.join("assets/index-3f2a1b.js"), "console.log('hi')").unwrap();
+
+        let config = financetracker::Config { static_asset_max_age_secs: 600, ..financetracker::Config::default() };
+        let app = financetracker::frontend_router(dist_dir.to_str().unwrap(), &config);
+
+        let get = |uri: &str| {
+            axum::http::Request::builder()
+                .method("GET")
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(get("/assets/index-3f2a1b.js")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert_eq!(response.headers()["cache-control"], "public, max-age=600, immutable");
+
+        for uri in ["/", "/index.html", "/budgets/some-spa-route"] {
+            let response = app.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK, "unexpected status for {}", uri);
+            assert_eq!(response.headers()["cache-control"], "no-cache", "unexpected cache-control for {}", uri);
+        }
+
+        // a missing asset isn't cached
+        let response = app.oneshot(get("/assets/missing.js")).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+        assert!(response.headers().get("cache-control").is_none());
+
+        std::fs::remove_dir_all(dist_dir).unwrap();
+    }
+
+    // check that an API-only deployment answers the root path with a JSON pointer to the API instead of the SPA
+    #[tokio::test]
+    async fn test_root_path_in_api_only_mode() {
+        let config = financetracker::Config { serve_frontend: false, ..financetracker::Config::default() };
+
+        let state = lazy_state(config);
+
+        let app = build_app(state);
+
+        let request = axum::http::Request::builder()
+            .method("GET")
+            .uri("/")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let info: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(info["name"], "financetracker");
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(info["api"], "/api");
+    }
+
+    // check that a request releases its slot under the concurrency limit once it's done, so back-to-back requests all get through
+    #[tokio::test]
+    async fn test_concurrency_limit_releases_slots() {
+        let config = financetracker::Config { max_concurrent_requests: 1, ..financetracker::Config::default() };
+
+        let state = lazy_state(config);
+
+        let app = build_app(state);
+
+        for _ in 0..3 {
+            let request = axum::http::Request::builder()
+                .method("GET")
+                .uri("/api/test")
+                .body(axum::body::Body::empty())
+                .unwrap();
+
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK);
+        }
+    }
+
+    // check that a request arriving while every slot is taken is shed with a 503 and a Retry-After header
+    #[tokio::test]
+    async fn test_concurrency_limit_sheds_when_full() {
+        let config = financetracker::Config { max_concurrent_requests: 1, ..financetracker::Config::default() };
+
+        let state = lazy_state(config);
+
+        let app = build_app(state);
+
+        // hold the only slot with a request whose body doesn't arrive until we say so
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let body = futures_util::stream::once(async move {
+            let _ = released.await;
+            Ok::<_, std::io::Error>(axum::body::Bytes::from_static(b"{}"))
+        });
+        let held = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/users/register")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from_stream(body))
+            .unwrap();
+        let held = tokio::spawn(app.clone().oneshot(held));
+
+        // once the held request has its slot, the next one is turned away
+        let mut shed = None;
+        for _ in 0..100 {
+            let request = axum::http::Request::builder()
+                .method("GET")
+                .uri("/api/test")
+                .body(axum::body::Body::empty())
+                .unwrap();
+
+            let response = app.clone().oneshot(request).await.unwrap();
+            if response.status() == axum::http::StatusCode::SERVICE_UNAVAILABLE {
+                shed = Some(response);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let shed = shed.expect("a request should be shed while the only slot is held");
+        assert!(shed.headers().contains_key(axum::http::header::RETRY_AFTER));
+
+        // letting the held request finish frees its slot again
+        release.send(()).unwrap();
+        held.await.unwrap().unwrap();
+
+        let request = axum::http::Request::builder()
+            .method("GET")
+            .uri("/api/test")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+    }
+
+    // check that losing the database connection is answered 503 with a Retry-After header, not a bare 500
+    #[tokio::test]
+    async fn test_database_unavailable_is_503() {
+        // nothing listens on port 1, so every connection attempt fails
+        let mut state = lazy_state(financetracker::Config::default());
+        state.pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_secs(1))
+            .connect_lazy("postgres://financetracker@127.0.0.1:1/financetracker")
+            .unwrap();
+
+        let app = build_app(state);
+
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/users/login")
+            .header("Content-Type", "application/json")
+            .body(axum::body::Body::from(r#"{"identifier":"someone","password":"password123"}"#))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().contains_key(axum::http::header::RETRY_AFTER));
+    }
+
     // jwt testing
 
 }
\ No newline at end of file
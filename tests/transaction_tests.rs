@@ -0,0 +1,1027 @@
+mod common;
+
+use financetracker::{archive_old_transactions, build_app};
+
+// use the test module
+#[cfg(test)]
+mod transaction_tests {
+    use super::*;
+
+    // test that recategorizing a batch of transactions moves their spend into the new category's budget progress
+    #[tokio::test]
+    async fn test_recategorize_transactions_updates_budget_progress() {
+        // set up app state and register + log in a test user
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // seed two miscategorized expenses and one that should stay where it is
+        let first = common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Misc"), "2026-02-03", Some("NoFrills")).await;
+        let second = common::insert_test_transaction(&state, user_id, "15.50", "expense", None, "2026-02-10", Some("SaveOn")).await;
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Misc"), "2026-02-12", Some("Gift")).await;
+
+        // set a groceries budget for the month
+        let budget = serde_json::json!({ "month": "2026-02-01", "category": "Groceries", "amount": 100 });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(budget)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        // recategorize the two grocery transactions
+        let body = serde_json::json!({ "ids": [first, second], "category": "Groceries" });
+        let (status, response) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(response["updated"], 2);
+
+        // the groceries budget should now count both recategorized transactions as spent
+        let (status, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-02-01", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(progress[0]["category"], "Groceries");
+        assert_eq!(progress[0]["spent"], "35.50");
+    }
+
+    // test that recategorizing is all-or-nothing when one of the ids belongs to someone else
+    #[tokio::test]
+    async fn test_recategorize_rejects_foreign_transactions() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username1, password1) = common::create_and_register_test_user(&app).await;
+        let (user_id1, access_token1) = common::login_test_user(&app, &username1, &password1).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (user_id2, _access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        let own = common::insert_test_transaction(&state, user_id1, "10.00", "expense", Some("Misc"), "2026-02-03", None).await;
+        let foreign = common::insert_test_transaction(&state, user_id2, "10.00", "expense", Some("Misc"), "2026-02-03", None).await;
+
+        // the first user tries to recategorize their own transaction along with the second user's
+        let body = serde_json::json!({ "ids": [own, foreign], "category": "Groceries" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token1), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+
+        // neither transaction should have changed
+        let categories: Vec<Option<String>> = sqlx::query_scalar("SELECT category FROM transactions WHERE id = ANY($1)")
+            .bind(vec![own, foreign])
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        assert!(categories.iter().all(|c| c.as_deref() == Some("Misc")));
+    }
+
+    // test that the archival job moves old transactions out of the main list and into the archive
+    #[tokio::test]
+    async fn test_archive_old_transactions() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let old = common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("Food"), "2001-03-04", None).await;
+        let recent = common::insert_test_transaction(&state, user_id, "8.00", "expense", Some("Food"), "2026-03-04", None).await;
+
+        // archive anything older than 10 years, twice, to make sure re-running is harmless
+        archive_old_transactions(&state.pool, 10).await.unwrap();
+        archive_old_transactions(&state.pool, 10).await.unwrap();
+
+        // only the recent transaction should be left in the main list
+        let (status, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let ids: Vec<&str> = transactions.as_array().unwrap().iter().map(|t| t["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec![recent.to_string()]);
+
+        // and the old one should show up (once) in the archive
+        let (status, archived) = common::send_request(&app, "GET", "/api/transactions/archived", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let archived_ids: Vec<&str> = archived.as_array().unwrap().iter().map(|t| t["id"].as_str().unwrap()).collect();
+        assert_eq!(archived_ids, vec![old.to_string()]);
+    }
+
+    // test that an income source can't be set on an expense
+    #[tokio::test]
+    async fn test_source_rejected_on_expense() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({
+            "amount": 25.00,
+            "kind": "Expense",
+            "date": "2026-01-07",
+            "category": "Food",
+            "source": "Salary"
+        });
+
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that pending transactions can be filtered, don't count towards budgets, and can be cleared
+    #[tokio::test]
+    async fn test_pending_transactions_filter_and_clear() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // one cleared and one pending expense in the same budgeted category
+        common::insert_test_transaction(&state, user_id, "30.00", "expense", Some("Dining"), "2026-03-05", None).await;
+        let pending = common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("Dining"), "2026-03-06", None).await;
+        sqlx::query("UPDATE transactions SET status = 'pending' WHERE id = $1")
+            .bind(pending)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        let budget = serde_json::json!({ "month": "2026-03-01", "category": "Dining", "amount": 200 });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(budget)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        // the status filter should only return the pending transaction
+        let (status, transactions) = common::send_request(&app, "GET", "/api/transactions?status=Pending", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let transactions = transactions.as_array().unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0]["id"], pending.to_string());
+        assert_eq!(transactions[0]["status"], "Pending");
+
+        // the pending charge shouldn't count as spent yet
+        let (_, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-03-01", Some(&access_token), None).await;
+        assert_eq!(progress[0]["spent"], "30.00");
+
+        // clear it, and it should now count
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/transactions/{}/clear", pending), Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        let (_, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-03-01", Some(&access_token), None).await;
+        assert_eq!(progress[0]["spent"], "42.00");
+
+        let (_, pending_list) = common::send_request(&app, "GET", "/api/transactions?status=Pending", Some(&access_token), None).await;
+        assert_eq!(pending_list, serde_json::json!([]));
+    }
+
+    // test that a user can't clear someone else's transaction
+    #[tokio::test]
+    async fn test_clear_foreign_transaction_not_found() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username1, password1) = common::create_and_register_test_user(&app).await;
+        let (_user_id1, access_token1) = common::login_test_user(&app, &username1, &password1).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (user_id2, _access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        let foreign = common::insert_test_transaction(&state, user_id2, "5.00", "expense", None, "2026-03-06", None).await;
+
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/transactions/{}/clear", foreign), Some(&access_token1), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    // test deleting a transaction by id, and that a missing or someone else's transaction is a 404
+    #[tokio::test]
+    async fn test_delete_transaction() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username1, password1) = common::create_and_register_test_user(&app).await;
+        let (user_id1, access_token1) = common::login_test_user(&app, &username1, &password1).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (user_id2, access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        let own = common::insert_test_transaction(&state, user_id1, "5.00", "expense", None, "2026-03-06", None).await;
+        let foreign = common::insert_test_transaction(&state, user_id2, "7.00", "expense", None, "2026-03-06", None).await;
+
+        // the listed id is the one to delete
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token1), None).await;
+        assert_eq!(transactions[0]["id"], own.to_string());
+
+        let (status, _) = common::send_request(&app, "DELETE", &format!("/api/transactions/{}", own), Some(&access_token1), None).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token1), None).await;
+        assert_eq!(transactions, serde_json::json!([]));
+
+        // already gone
+        let (status, _) = common::send_request(&app, "DELETE", &format!("/api/transactions/{}", own), Some(&access_token1), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+
+        // someone else's is left alone
+        let (status, _) = common::send_request(&app, "DELETE", &format!("/api/transactions/{}", foreign), Some(&access_token1), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token2), None).await;
+        assert_eq!(transactions.as_array().unwrap().len(), 1);
+    }
+
+    // test editing a transaction's amount and category, keeping its date and budget flag, and that someone else's is a 404
+    #[tokio::test]
+    async fn test_update_transaction() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username1, password1) = common::create_and_register_test_user(&app).await;
+        let (user_id1, access_token1) = common::login_test_user(&app, &username1, &password1).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (user_id2, _access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        // (inserted directly, so there's no embedding to refresh and no call out to OpenAI)
+        let own = common::insert_test_transaction(&state, user_id1, "12.00", "expense", Some("Fod"), "2026-03-06", Some("Lunch")).await;
+        let foreign = common::insert_test_transaction(&state, user_id2, "7.00", "expense", None, "2026-03-06", None).await;
+        sqlx::query("UPDATE transactions SET exclude_from_budget = true WHERE id = $1")
+            .bind(own)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        let body = serde_json::json!({ "amount": "21.00", "kind": "Expense", "category": "Food", "description": "Lunch" });
+        let (status, updated) = common::send_request(&app, "PUT", &format!("/api/transactions/{}", own), Some(&access_token1), Some(body.clone())).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(updated["id"], own.to_string());
+        assert_eq!(updated["amount"], "21.00");
+        assert_eq!(updated["category"], "Food");
+        assert_eq!(updated["date"], "2026-03-06");
+        assert_eq!(updated["exclude_from_budget"], true);
+
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token1), None).await;
+        assert_eq!(transactions[0]["amount"], "21.00");
+        assert_eq!(transactions[0]["category"], "Food");
+
+        let (status, _) = common::send_request(&app, "PUT", &format!("/api/transactions/{}", foreign), Some(&access_token1), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    // test duplicating a transaction to a given date, and to today when there's no body
+    #[tokio::test]
+    async fn test_duplicate_transaction() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let original = common::insert_test_transaction(&state, user_id, "12.50", "expense", Some("Coffee"), "2026-02-01", Some("Beans")).await;
+
+        let body = serde_json::json!({ "date": "2026-02-15" });
+        let (status, copy) = common::send_request(&app, "POST", &format!("/api/transactions/{}/duplicate", original), Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_ne!(copy["id"], original.to_string());
+        assert_eq!(copy["date"], "2026-02-15");
+        assert_eq!(copy["amount"], "12.50");
+        assert_eq!(copy["category"], "Coffee");
+        assert_eq!(copy["description"], "Beans");
+
+        let (status, copy) = common::send_request(&app, "POST", &format!("/api/transactions/{}/duplicate", original), Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(copy["date"], chrono::Utc::now().date_naive().to_string());
+
+        // the original plus two copies
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(transactions.as_array().unwrap().len(), 3);
+    }
+
+    // test that duplicating someone else's transaction is a 404
+    #[tokio::test]
+    async fn test_duplicate_foreign_transaction_not_found() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username1, password1) = common::create_and_register_test_user(&app).await;
+        let (_user_id1, access_token1) = common::login_test_user(&app, &username1, &password1).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (user_id2, _access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        let foreign = common::insert_test_transaction(&state, user_id2, "5.00", "expense", None, "2026-03-06", None).await;
+
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/transactions/{}/duplicate", foreign), Some(&access_token1), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    // test cloning a month's transactions, with days past the end of the target month clamped to its last day
+    #[tokio::test]
+    async fn test_clone_month_clamps_month_end() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "1200.00", "expense", Some("Rent"), "2026-01-15", None).await;
+        common::insert_test_transaction(&state, user_id, "40.00", "expense", Some("Phone"), "2026-01-31", None).await;
+        common::insert_test_transaction(&state, user_id, "9.99", "expense", Some("Music"), "2026-02-03", None).await;
+
+        let body = serde_json::json!({ "from_month": "2026-01-01", "to_month": "2026-02-01" });
+        let (status, result) = common::send_request(&app, "POST", "/api/transactions/clone-month", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(result["created"], 2);
+        assert_eq!(result["clamped"], serde_json::json!([{ "original": "2026-01-31", "date": "2026-02-28" }]));
+
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        let mut february: Vec<(String, String)> = transactions.as_array().unwrap().iter()
+            .filter(|t| t["date"].as_str().unwrap().starts_with("2026-02"))
+            .map(|t| (t["date"].as_str().unwrap().to_string(), t["category"].as_str().unwrap().to_string()))
+            .collect();
+        february.sort();
+        assert_eq!(february, vec![
+            ("2026-02-03".to_string(), "Music".to_string()),
+            ("2026-02-15".to_string(), "Rent".to_string()),
+            ("2026-02-28".to_string(), "Phone".to_string()),
+        ]);
+
+        // cloning a month onto itself is rejected
+        let body = serde_json::json!({ "from_month": "2026-01-01", "to_month": "2026-01-20" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/clone-month", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that cloning into a future month is refused unless future transactions are allowed
+    #[tokio::test]
+    async fn test_clone_month_rejects_future_dates() {
+        let mut state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "1200.00", "expense", Some("Rent"), "2026-01-15", None).await;
+
+        let body = serde_json::json!({ "from_month": "2026-01-01", "to_month": "2099-01-01" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/clone-month", Some(&access_token), Some(body.clone())).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // nothing was copied
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(transactions.as_array().unwrap().len(), 1);
+
+        // with planned transactions allowed, the same clone goes through
+        state.config.allow_future_transactions = true;
+        let app = build_app(state);
+        let (status, result) = common::send_request(&app, "POST", "/api/transactions/clone-month", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(result["created"], 1);
+    }
+
+    // test that recent categories come back by recency by default, and by frequency when asked
+    #[tokio::test]
+    async fn test_recent_categories_ordering() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // Food is used most often, Travel most recently, and Gym once in between
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", Some("Food"), "2026-01-02", None).await;
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", Some("Food"), "2026-01-03", None).await;
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", Some("Food"), "2026-01-04", None).await;
+        common::insert_test_transaction(&state, user_id, "50.00", "expense", Some("Gym"), "2026-01-10", None).await;
+        common::insert_test_transaction(&state, user_id, "300.00", "expense", Some("Travel"), "2026-01-20", None).await;
+        common::insert_test_transaction(&state, user_id, "5.00", "expense", None, "2026-01-25", None).await;
+
+        let (status, recent) = common::send_request(&app, "GET", "/api/categories/recent", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let recent: Vec<&str> = recent.as_array().unwrap().iter().map(|c| c["category"].as_str().unwrap()).collect();
+        assert_eq!(recent, vec!["Travel", "Gym", "Food"]);
+
+        let (status, frequent) = common::send_request(&app, "GET", "/api/categories/recent?order=frequent&limit=2", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(frequent[0]["category"], "Food");
+        assert_eq!(frequent[0]["uses"], 3);
+        assert_eq!(frequent[0]["last_used"], "2026-01-04");
+        assert_eq!(frequent.as_array().unwrap().len(), 2);
+    }
+
+    // test that description suggestions match the prefix case-insensitively and come back most used first
+    #[tokio::test]
+    async fn test_description_suggestions() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // coffee shop is used most, coffee beans once, and the rest don't start with "co"
+        common::insert_test_transaction(&state, user_id, "4.50", "expense", Some("Food"), "2026-01-02", Some("Coffee shop")).await;
+        common::insert_test_transaction(&state, user_id, "4.50", "expense", Some("Food"), "2026-01-03", Some("Coffee shop")).await;
+        common::insert_test_transaction(&state, user_id, "4.50", "expense", Some("Food"), "2026-01-09", Some("Coffee shop")).await;
+        common::insert_test_transaction(&state, user_id, "18.00", "expense", Some("Food"), "2026-01-05", Some("Coffee beans")).await;
+        common::insert_test_transaction(&state, user_id, "9.00", "expense", Some("Transport"), "2026-01-06", Some("Commute")).await;
+        common::insert_test_transaction(&state, user_id, "9.00", "expense", Some("Transport"), "2026-01-07", Some("Commute")).await;
+        common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("Food"), "2026-01-08", Some("Lunch with coworkers")).await;
+
+        let (status, suggestions) = common::send_request(&app, "GET", "/api/transactions/descriptions?prefix=co", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(suggestions, serde_json::json!([
+            { "description": "Coffee shop", "uses": 3, "last_used": "2026-01-09" },
+            { "description": "Commute", "uses": 2, "last_used": "2026-01-07" },
+            { "description": "Coffee beans", "uses": 1, "last_used": "2026-01-05" },
+        ]));
+
+        let (_, suggestions) = common::send_request(&app, "GET", "/api/transactions/descriptions?prefix=COFFEE%20B&limit=5", Some(&access_token), None).await;
+        assert_eq!(suggestions.as_array().unwrap().len(), 1);
+        assert_eq!(suggestions[0]["description"], "Coffee beans");
+
+        // LIKE wildcards in the prefix are matched literally, and the limit caps the list
+        let (_, suggestions) = common::send_request(&app, "GET", "/api/transactions/descriptions?prefix=%25", Some(&access_token), None).await;
+        assert_eq!(suggestions, serde_json::json!([]));
+        let (_, suggestions) = common::send_request(&app, "GET", "/api/transactions/descriptions?prefix=&limit=2", Some(&access_token), None).await;
+        assert_eq!(suggestions.as_array().unwrap().len(), 2);
+        assert_eq!(suggestions[0]["description"], "Coffee shop");
+    }
+
+    // helper function to list the descriptions of the user's remaining transactions, sorted
+    async fn remaining_descriptions(app: &axum::Router, access_token: &str) -> Vec<String> {
+        let (_, transactions) = common::send_request(app, "GET", "/api/transactions", Some(access_token), None).await;
+        let mut descriptions: Vec<String> = transactions
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["description"].as_str().unwrap_or("").to_string())
+            .collect();
+        descriptions.sort();
+        descriptions
+    }
+
+    // test bulk deleting by a list of ids, scoped to the caller
+    #[tokio::test]
+    async fn test_bulk_delete_by_ids() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username1, password1) = common::create_and_register_test_user(&app).await;
+        let (user_id1, access_token1) = common::login_test_user(&app, &username1, &password1).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (user_id2, access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        let a = common::insert_test_transaction(&state, user_id1, "1.00", "expense", None, "2026-01-01", Some("a")).await;
+        let b = common::insert_test_transaction(&state, user_id1, "2.00", "expense", None, "2026-01-02", Some("b")).await;
+        common::insert_test_transaction(&state, user_id1, "3.00", "expense", None, "2026-01-03", Some("c")).await;
+        let foreign = common::insert_test_transaction(&state, user_id2, "4.00", "expense", None, "2026-01-04", Some("d")).await;
+
+        // without confirmation nothing happens
+        let body = serde_json::json!({ "ids": [a, b] });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token1), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // with confirmation, only the caller's own transactions are deleted
+        let body = serde_json::json!({ "ids": [a, b, foreign], "confirm": true });
+        let (status, response) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token1), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(response["deleted"], 2);
+
+        assert_eq!(remaining_descriptions(&app, &access_token1).await, vec!["c"]);
+        assert_eq!(remaining_descriptions(&app, &access_token2).await, vec!["d"]);
+    }
+
+    // test bulk deleting by a date range and category filter
+    #[tokio::test]
+    async fn test_bulk_delete_by_filter() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "1.00", "expense", Some("Imported"), "2026-01-05", Some("in range")).await;
+        common::insert_test_transaction(&state, user_id, "2.00", "expense", Some("Imported"), "2026-01-25", Some("also in range")).await;
+        common::insert_test_transaction(&state, user_id, "3.00", "expense", Some("Imported"), "2026-02-05", Some("too late")).await;
+        common::insert_test_transaction(&state, user_id, "4.00", "expense", Some("Food"), "2026-01-10", Some("other category")).await;
+
+        // an empty filter is refused, even when confirmed
+        let body = serde_json::json!({ "confirm": true });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let body = serde_json::json!({ "from": "2026-01-01", "to": "2026-01-31", "category": "Imported", "confirm": true });
+        let (status, response) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(response["deleted"], 2);
+
+        assert_eq!(remaining_descriptions(&app, &access_token).await, vec!["other category", "too late"]);
+    }
+
+    // test that transactions in a closed month can't be added, changed or deleted until the month is reopened
+    #[tokio::test]
+    async fn test_closed_month_blocks_changes() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let january = common::insert_test_transaction(&state, user_id, "10.00", "expense", Some("Food"), "2026-01-15", None).await;
+        let february = common::insert_test_transaction(&state, user_id, "20.00", "expense", Some("Food"), "2026-02-15", None).await;
+
+        // any day in the month closes the whole month
+        let (status, _) = common::send_request(&app, "POST", "/api/months/2026-01-20/close", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        // adding into the closed month is refused (before any embedding work happens)
+        let body = serde_json::json!({ "amount": "5.00", "kind": "Expense", "category": "Food", "date": "2026-01-31", "description": null });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::LOCKED);
+
+        let body = serde_json::json!({ "ids": [january, february], "category": "Groceries" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::LOCKED);
+
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/transactions/{}/clear", january), Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::LOCKED);
+
+        let body = serde_json::json!({ "from": "2026-01-01", "to": "2026-02-28", "confirm": true });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::LOCKED);
+
+        // the open month is unaffected
+        let body = serde_json::json!({ "ids": [february], "category": "Groceries" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        // after reopening, the January transaction can be changed again
+        let (status, _) = common::send_request(&app, "POST", "/api/months/2026-01-01/reopen", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        let body = serde_json::json!({ "ids": [january], "category": "Groceries" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        // reopening a month that isn't closed is a 404
+        let (status, _) = common::send_request(&app, "POST", "/api/months/2026-01-01/reopen", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    // test the default newest-first order and each of the sort options
+    #[tokio::test]
+    async fn test_transaction_sort_order() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // inserted in this order, so created_at order is a, b, c
+        common::insert_test_transaction(&state, user_id, "30.00", "expense", None, "2026-01-10", Some("a")).await;
+        common::insert_test_transaction(&state, user_id, "10.00", "expense", None, "2026-01-20", Some("b")).await;
+        common::insert_test_transaction(&state, user_id, "20.00", "expense", None, "2026-01-05", Some("c")).await;
+
+        let cases = [
+            ("", vec!["b", "a", "c"]),
+            ("?order_by=date&order_dir=asc", vec!["c", "a", "b"]),
+            ("?order_by=amount", vec!["a", "c", "b"]),
+            ("?order_by=amount&order_dir=asc", vec!["b", "c", "a"]),
+            ("?order_by=created_at&order_dir=asc", vec!["a", "b", "c"]),
+            ("?order_by=created_at&order_dir=desc", vec!["c", "b", "a"]),
+        ];
+
+        for (params, expected) in cases {
+            let (status, transactions) = common::send_request(&app, "GET", &format!("/api/transactions{}", params), Some(&access_token), None).await;
+            assert_eq!(status, axum::http::StatusCode::OK);
+            let descriptions: Vec<&str> = transactions.as_array().unwrap().iter().map(|t| t["description"].as_str().unwrap()).collect();
+            assert_eq!(descriptions, expected, "unexpected order for {:?}", params);
+        }
+
+        // anything outside the whitelist is rejected rather than reaching the query
+        let (status, _) = common::send_request(&app, "GET", "/api/transactions?order_by=password_hash", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that bulk requests over the configured item limit are rejected before anything is changed
+    #[tokio::test]
+    async fn test_bulk_item_limit() {
+        let mut state = common::setup_app_state().await;
+        state.config.max_bulk_items = 2;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let mut ids = Vec::new();
+        for day in ["2026-01-01", "2026-01-02", "2026-01-03"] {
+            ids.push(common::insert_test_transaction(&state, user_id, "1.00", "expense", Some("Food"), day, None).await);
+        }
+
+        let body = serde_json::json!({ "ids": ids, "category": "Groceries" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let body = serde_json::json!({ "ids": ids, "confirm": true });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // nothing was touched, and a request at the limit still goes through
+        let body = serde_json::json!({ "ids": &ids[..2], "category": "Groceries" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(transactions.as_array().unwrap().len(), 3);
+    }
+
+    // test that over-length descriptions, categories, merchants and locations are rejected before anything is stored
+    #[tokio::test]
+    async fn test_add_transaction_rejects_overlong_fields() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "amount": "5.00", "kind": "Expense", "category": "Food", "date": "2026-01-31", "description": "d".repeat(1001) });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let body = serde_json::json!({ "amount": "5.00", "kind": "Expense", "category": "c".repeat(65), "date": "2026-01-31", "description": null });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let body = serde_json::json!({ "amount": "5.00", "kind": "Expense", "category": "Food", "date": "2026-01-31", "merchant": "m".repeat(101) });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let body = serde_json::json!({ "amount": "5.00", "kind": "Expense", "category": "Food", "date": "2026-01-31", "location": "l".repeat(201) });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(transactions, serde_json::json!([]));
+    }
+
+    // test that amounts too large, or with more decimal places than the default 2, are a 400 rather than a database error
+    #[tokio::test]
+    async fn test_add_transaction_rejects_out_of_range_amount() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        for amount in ["10000000000000.00", "-10000000000000", "5.001"] {
+            let body = serde_json::json!({ "amount": amount, "kind": "Expense", "category": "Food", "date": "2026-01-31", "description": null });
+            let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::BAD_REQUEST, "amount {}", amount);
+        }
+
+        // budgets share the same column bounds
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "Food", "amount": "10000000000000" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // the largest amount that fits, with trailing zeros past the scale, is fine
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "Food", "amount": "9999999999999.990" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+    }
+
+    // test that a user with a higher amount scale can use more decimal places, and that sums over them stay exact
+    #[tokio::test]
+    async fn test_high_precision_amount_scale() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // scales past the maximum are rejected
+        let body = serde_json::json!({ "amount_scale": 9 });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let body = serde_json::json!({ "amount_scale": 8 });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        let (_, bootstrap) = common::send_request(&app, "GET", "/api/me/bootstrap", Some(&access_token), None).await;
+        assert_eq!(bootstrap["profile"]["amount_scale"], 8);
+
+        // 9 places is still too many
+        let body = serde_json::json!({ "amount": "0.000123456", "kind": "Expense", "category": "Crypto", "date": "2026-01-31", "description": null });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // 8 place amounts are stored and summed exactly, alongside ordinary 2 place ones
+        common::insert_test_transaction(&state, user_id, "0.00012345", "expense", Some("Crypto"), "2026-01-10", None).await;
+        common::insert_test_transaction(&state, user_id, "0.00000001", "expense", Some("Crypto"), "2026-01-11", None).await;
+        common::insert_test_transaction(&state, user_id, "12.50", "expense", Some("Crypto"), "2026-01-12", None).await;
+
+        let budget = serde_json::json!({ "month": "2026-01-01", "category": "Crypto", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(budget)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (_, progress) = common::send_request(&app, "GET", "/api/budgets/progress?month=2026-01-01", Some(&access_token), None).await;
+        assert_eq!(progress[0]["spent"], "12.50012346");
+
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions?order_by=amount&order_dir=asc", Some(&access_token), None).await;
+        let amounts: Vec<&str> = transactions.as_array().unwrap().iter().map(|t| t["amount"].as_str().unwrap()).collect();
+        assert_eq!(amounts, vec!["0.00000001", "0.00012345", "12.50"]);
+
+        // budgets stay at whole cents whatever the user's scale
+        let budget = serde_json::json!({ "month": "2026-01-01", "category": "Crypto", "amount": "100.001" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(budget)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that a transaction posted without a date is stored with today's date (this one goes through the embedding call)
+    #[tokio::test]
+    async fn test_add_transaction_defaults_to_today() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "amount": "7.25", "kind": "Expense", "category": "Coffee", "description": "Latte" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(transactions[0]["date"], chrono::Utc::now().date_naive().to_string());
+    }
+
+    // test that future-dated transactions are rejected by default
+    #[tokio::test]
+    async fn test_future_transactions_rejected_by_default() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let tomorrow = chrono::Utc::now().date_naive() + chrono::Days::new(1);
+
+        let body = serde_json::json!({ "amount": "80.00", "kind": "Expense", "category": "Bills", "date": tomorrow, "description": null });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let original = common::insert_test_transaction(&state, user_id, "80.00", "expense", Some("Bills"), "2026-01-15", None).await;
+        let body = serde_json::json!({ "date": tomorrow });
+        let (status, _) = common::send_request(&app, "POST", &format!("/api/transactions/{}/duplicate", original), Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that with future transactions allowed, a planned one is accepted but doesn't count as spent until its date
+    #[tokio::test]
+    async fn test_future_transactions_allowed() {
+        let mut state = common::setup_app_state().await;
+        state.config.allow_future_transactions = true;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // a bill paid today, planned again for 40 days' time (in a later month either way)
+        let today = chrono::Utc::now().date_naive();
+        let planned_date = today + chrono::Days::new(40);
+        let month = chrono::Datelike::with_day(&planned_date, 1).unwrap();
+
+        let paid = common::insert_test_transaction(&state, user_id, "80.00", "expense", Some("Bills"), &today.to_string(), None).await;
+        let body = serde_json::json!({ "date": planned_date });
+        let (status, planned) = common::send_request(&app, "POST", &format!("/api/transactions/{}/duplicate", paid), Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(planned["date"], planned_date.to_string());
+
+        // it's budgeted for, but nothing has been spent in that month yet
+        let body = serde_json::json!({ "month": month, "category": "Bills", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (_, progress) = common::send_request(&app, "GET", &format!("/api/budgets/progress?month={}", month), Some(&access_token), None).await;
+        assert_eq!(progress[0]["spent"], "0");
+
+        let (_, forecast) = common::send_request(&app, "GET", &format!("/api/analytics/forecast?month={}", month), Some(&access_token), None).await;
+        assert_eq!(forecast["categories"][0]["spent"], "0");
+    }
+
+    // test that category rules categorize matching transactions, first rule by priority winning
+    #[tokio::test]
+    async fn test_apply_category_rules() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // "star" would also match, but the more specific rule has the lower priority number so it runs first
+        for (match_contains, category, priority) in [("star", "Space", 5), ("starbucks", "Coffee", 1)] {
+            let body = serde_json::json!({ "match_contains": match_contains, "category": category, "priority": priority });
+            let (status, _) = common::send_request(&app, "POST", "/api/categories/rules", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        let (_, rules) = common::send_request(&app, "GET", "/api/categories/rules", Some(&access_token), None).await;
+        let categories: Vec<&str> = rules.as_array().unwrap().iter().map(|r| r["category"].as_str().unwrap()).collect();
+        assert_eq!(categories, vec!["Coffee", "Space"]);
+
+        let uncategorized = common::insert_test_transaction(&state, user_id, "5.75", "expense", None, "2026-03-02", Some("STARBUCKS #1234")).await;
+        let categorized = common::insert_test_transaction(&state, user_id, "25.00", "expense", Some("Gifts"), "2026-03-03", Some("Starbucks gift card")).await;
+        let unmatched = common::insert_test_transaction(&state, user_id, "60.00", "expense", None, "2026-03-04", Some("Grocery run")).await;
+
+        // by default only uncategorized transactions are touched
+        let (status, result) = common::send_request(&app, "POST", "/api/transactions/apply-rules", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(result["updated"], 1);
+
+        // with "all", already categorized ones are too (and ones already right aren't counted again)
+        let body = serde_json::json!({ "all": true });
+        let (_, result) = common::send_request(&app, "POST", "/api/transactions/apply-rules", Some(&access_token), Some(body)).await;
+        assert_eq!(result["updated"], 1);
+
+        let categories: Vec<(uuid::Uuid, Option<String>)> = sqlx::query_as("SELECT id, category FROM transactions WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        let category_of = |id| categories.iter().find(|(tid, _)| *tid == id).unwrap().1.clone();
+        assert_eq!(category_of(uncategorized).as_deref(), Some("Coffee"));
+        assert_eq!(category_of(categorized).as_deref(), Some("Coffee"));
+        assert_eq!(category_of(unmatched), None);
+
+        // empty matches are rejected, since they'd match everything
+        let body = serde_json::json!({ "match_contains": "  ", "category": "Everything" });
+        let (status, _) = common::send_request(&app, "POST", "/api/categories/rules", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // rules can be deleted, once
+        let rule_id = rules[0]["id"].as_str().unwrap();
+        let (status, _) = common::send_request(&app, "DELETE", &format!("/api/categories/rules/{}", rule_id), Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+        let (status, _) = common::send_request(&app, "DELETE", &format!("/api/categories/rules/{}", rule_id), Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    // test that with ENCRYPT_NOTES on, encrypted and legacy plaintext descriptions both read back, and rules still match
+    #[tokio::test]
+    async fn test_encrypted_notes_and_legacy_plaintext() {
+        let mut state = common::setup_app_state().await;
+        state.config.encrypt_notes = true;
+        state.config.notes_encryption_secret = Some("test-notes-secret".to_string());
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // one row written before encryption was turned on, one written after
+        let sealed = financetracker::notes::encrypt_note("test-notes-secret", user_id, "Corner bakery");
+        let legacy = common::insert_test_transaction(&state, user_id, "4.50", "expense", None, "2026-03-02", Some("Old plaintext note")).await;
+        let encrypted = common::insert_test_transaction(&state, user_id, "6.00", "expense", None, "2026-03-03", Some(&sealed)).await;
+
+        let (status, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let description_of = |id: uuid::Uuid| {
+            transactions.as_array().unwrap().iter()
+                .find(|t| t["id"] == id.to_string())
+                .map(|t| t["description"].as_str().unwrap().to_string())
+                .unwrap()
+        };
+        assert_eq!(description_of(legacy), "Old plaintext note");
+        assert_eq!(description_of(encrypted), "Corner bakery");
+
+        // the stored value stays ciphertext, and a duplicate keeps it that way
+        let (status, copy) = common::send_request(&app, "POST", &format!("/api/transactions/{}/duplicate", encrypted), Some(&access_token), Some(serde_json::json!({ "date": "2026-03-04" }))).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(copy["description"], "Corner bakery");
+
+        let stored: Vec<String> = sqlx::query_scalar("SELECT description FROM transactions WHERE user_id = $1 AND id <> $2")
+            .bind(user_id)
+            .bind(legacy)
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored.iter().all(|d| d.starts_with(financetracker::notes::ENCRYPTED_NOTE_PREFIX)));
+
+        // category rules match on the decrypted description
+        let body = serde_json::json!({ "match_contains": "bakery", "category": "Food" });
+        let (status, _) = common::send_request(&app, "POST", "/api/categories/rules", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        let (_, result) = common::send_request(&app, "POST", "/api/transactions/apply-rules", Some(&access_token), None).await;
+        assert_eq!(result["updated"], 2);
+    }
+
+    // test that concurrent inserts each get their own sequential reference, with no gaps or duplicates
+    #[tokio::test]
+    async fn test_concurrent_inserts_get_unique_references() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // the pool has several connections, so these really do race each other in the database
+        let inserts = (0..20).map(|_| common::insert_test_transaction(&state, user_id, "1.00", "expense", None, "2026-03-01", None));
+        futures_util::future::join_all(inserts).await;
+
+        let (status, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let mut references: Vec<String> = transactions.as_array().unwrap().iter()
+            .map(|t| t["reference"].as_str().unwrap().to_string())
+            .collect();
+        references.sort();
+        let expected: Vec<String> = (1..=20).map(|seq| format!("TXN-{:05}", seq)).collect();
+        assert_eq!(references, expected);
+    }
+
+    // test that modified_since returns only the transactions changed since then, plus tombstones for deleted ones
+    #[tokio::test]
+    async fn test_transactions_modified_since() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let changed = common::insert_test_transaction(&state, user_id, "12.00", "expense", None, "2026-03-01", None).await;
+        let deleted = common::insert_test_transaction(&state, user_id, "30.00", "expense", None, "2026-03-02", None).await;
+        common::insert_test_transaction(&state, user_id, "45.00", "expense", None, "2026-03-03", None).await;
+
+        // the client's last sync, taken from the database's clock so it lines up with updated_at
+        let cursor: chrono::DateTime<chrono::Utc> = sqlx::query_scalar("SELECT clock_timestamp()")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+
+        let body = serde_json::json!({ "ids": [changed], "category": "Food" });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/recategorize", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let body = serde_json::json!({ "ids": [deleted], "confirm": true });
+        let (status, _) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let uri = format!("/api/transactions?modified_since={}", cursor.to_rfc3339_opts(chrono::SecondsFormat::Micros, true));
+        let (status, changes) = common::send_request(&app, "GET", &uri, Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let changes = changes.as_array().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0]["id"], changed.to_string());
+        assert_eq!(changes[0]["category"], "Food");
+        assert_eq!(changes[1]["id"], deleted.to_string());
+        assert_eq!(changes[1]["deleted"], true);
+
+        // without the parameter it's the usual full list
+        let (_, transactions) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(transactions.as_array().unwrap().len(), 2);
+    }
+
+    // test the transaction date range, for a new user with nothing yet and once there are transactions
+    #[tokio::test]
+    async fn test_transaction_range() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let (status, range) = common::send_request(&app, "GET", "/api/transactions/range", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(range, serde_json::json!({ "earliest": null, "latest": null, "count": 0 }));
+
+        common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("Food"), "2025-11-03", None).await;
+        common::insert_test_transaction(&state, user_id, "2000.00", "income", Some("Salary"), "2026-02-01", None).await;
+        common::insert_test_transaction(&state, user_id, "8.00", "expense", Some("Food"), "2026-01-15", None).await;
+
+        let (status, range) = common::send_request(&app, "GET", "/api/transactions/range", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(range, serde_json::json!({ "earliest": "2025-11-03", "latest": "2026-02-01", "count": 3 }));
+    }
+
+    // test that a user_id in a request body is ignored: writes always act as the token's user
+    #[tokio::test]
+    async fn test_body_user_id_is_ignored() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+        let (other_username, other_password) = common::create_and_register_test_user(&app).await;
+        let (other_id, other_token) = common::login_test_user(&app, &other_username, &other_password).await;
+
+        let other_transaction = common::insert_test_transaction(&state, other_id, "25.00", "expense", Some("Food"), "2026-01-10", None).await;
+
+        // a budget naming the other user still belongs to the caller
+        let body = serde_json::json!({ "user_id": other_id, "month": "2026-01-01", "category": "Food", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets?month=2026-01-01", Some(&access_token), None).await;
+        assert_eq!(budgets[0]["user_id"], user_id.to_string());
+        let (_, other_budgets) = common::send_request(&app, "GET", "/api/budgets?month=2026-01-01", Some(&other_token), None).await;
+        assert_eq!(other_budgets, serde_json::json!([]));
+
+        // naming the other user doesn't reach their transactions either
+        let body = serde_json::json!({ "user_id": other_id, "ids": [other_transaction], "confirm": true });
+        let (status, deleted) = common::send_request(&app, "POST", "/api/transactions/delete", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(deleted["deleted"], 0);
+
+        let body = serde_json::json!({ "user_id": other_id });
+        let uri = format!("/api/transactions/{}/duplicate", other_transaction);
+        let (status, _) = common::send_request(&app, "POST", &uri, Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+
+        let (_, range) = common::send_request(&app, "GET", "/api/transactions/range", Some(&other_token), None).await;
+        assert_eq!(range["count"], 1);
+    }
+
+    // test that ?signed=true negates expense amounts on listed transactions only, leaving income and the default alone
+    #[tokio::test]
+    async fn test_signed_amounts() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "100.00", "income", Some("Salary"), "2026-01-01", None).await;
+        common::insert_test_transaction(&state, user_id, "30.00", "expense", Some("Food"), "2026-01-02", None).await;
+
+        let amounts = |transactions: &serde_json::Value| -> Vec<String> {
+            transactions.as_array().unwrap().iter().map(|t| t["amount"].as_str().unwrap().to_string()).collect()
+        };
+
+        let (_, unsigned) = common::send_request(&app, "GET", "/api/transactions", Some(&access_token), None).await;
+        assert_eq!(amounts(&unsigned), vec!["30.00", "100.00"]);
+
+        let (status, signed) = common::send_request(&app, "GET", "/api/transactions?signed=true", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(amounts(&signed), vec!["-30.00", "100.00"]);
+
+        let (_, largest) = common::send_request(&app, "GET", "/api/analytics/largest?signed=true", Some(&access_token), None).await;
+        assert_eq!(amounts(&largest), vec!["-30.00"]);
+
+        // aggregates keep their meaning: cashflow's net was already signed
+        let (_, cashflow) = common::send_request(&app, "GET", "/api/analytics/cashflow?signed=true", Some(&access_token), None).await;
+        assert_eq!(cashflow[0]["net"], "70.00");
+
+        let (status, _) = common::send_request(&app, "GET", "/api/transactions?signed=maybe", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+}
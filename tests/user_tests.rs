@@ -0,0 +1,370 @@
+mod common;
+
+use financetracker::build_app;
+
+// use the test module
+#[cfg(test)]
+mod user_tests {
+    use super::*;
+
+    // helper function to build a registration body for a fresh unique user
+    fn unique_registration() -> serde_json::Value {
+        let username = format!("testuser_{}", uuid::Uuid::new_v4());
+        serde_json::json!({
+            "username": username,
+            "email": format!("{}@example.com", username),
+            "password": "bestPassword",
+        })
+    }
+
+    // test that rapid registrations from the same client are throttled after the limit
+    #[tokio::test]
+    async fn test_registration_rate_limit() {
+        let state = common::setup_app_state().await;
+        let limit = state.config.registration_rate_limit;
+        let app = build_app(state);
+
+        // the first `limit` registrations go through
+        for _ in 0..limit {
+            let (status, _) = common::send_request(&app, "POST", "/api/users/register", None, Some(unique_registration())).await;
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        // and the next one is rejected
+        let (status, _) = common::send_request(&app, "POST", "/api/users/register", None, Some(unique_registration())).await;
+        assert_eq!(status, axum::http::StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    // test that changing a password works, and that changing it back to the old one is rejected
+    #[tokio::test]
+    async fn test_password_change_rejects_reuse() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        // change to a new password
+        let body = serde_json::json!({ "current_password": password, "new_password": "evenBetterPassword" });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/me/password", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        // the new password works for logging in
+        common::login_test_user(&app, &username, "evenBetterPassword").await;
+
+        // but changing straight back to the old one is rejected
+        let body = serde_json::json!({ "current_password": "evenBetterPassword", "new_password": password });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/me/password", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that the current password has to be correct to change it
+    #[tokio::test]
+    async fn test_password_change_requires_current_password() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "current_password": "wrongPassword", "new_password": "evenBetterPassword" });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/me/password", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    // test changing a username, then logging in with the new one (and the old token still working)
+    #[tokio::test]
+    async fn test_change_username() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let new_username = format!("renamed_{}", uuid::Uuid::new_v4());
+        let body = serde_json::json!({ "username": new_username });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        // the new username logs in as the same user
+        let (renamed_user_id, _) = common::login_test_user(&app, &new_username, &password).await;
+        assert_eq!(renamed_user_id, user_id);
+
+        // and the existing token is still accepted
+        let (status, _) = common::send_request(&app, "GET", "/api/budgets", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+    }
+
+    // test that taken and malformed usernames are rejected
+    #[tokio::test]
+    async fn test_change_username_conflict_and_format() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state);
+        let (username1, _password1) = common::create_and_register_test_user(&app).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (_user_id2, access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        let body = serde_json::json!({ "username": username1 });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token2), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+
+        let body = serde_json::json!({ "username": "no spaces allowed" });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token2), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test the two-factor flow: login needs a code once enabled, backup codes work once, and disabling needs a code
+    #[tokio::test]
+    async fn test_two_factor_login() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let (status, setup) = common::send_request(&app, "POST", "/api/users/2fa/enable", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert!(setup["otpauth_uri"].as_str().unwrap().starts_with("otpauth://totp/"));
+        let backup_codes = setup["backup_codes"].as_array().unwrap();
+        assert_eq!(backup_codes.len(), 8);
+
+        // enabling twice is a conflict
+        let (status, _) = common::send_request(&app, "POST", "/api/users/2fa/enable", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+
+        // the password alone is no longer enough
+        let body = serde_json::json!({ "identifier": username, "password": password });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/login", None, Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+
+        // compute the current code the way an authenticator app would
+        let secret: Vec<u8> = sqlx::query_scalar("SELECT totp_secret FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+        let code = financetracker::totp::totp_code(&secret, now);
+
+        let body = serde_json::json!({ "identifier": username, "password": password, "totp_code": code });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/login", None, Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        // a backup code works exactly once
+        let body = serde_json::json!({ "identifier": username, "password": password, "totp_code": backup_codes[0] });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/login", None, Some(body.clone())).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let (status, _) = common::send_request(&app, "POST", "/api/users/login", None, Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+
+        // disabling needs a valid code (the spent backup code doesn't count)
+        let body = serde_json::json!({ "code": backup_codes[0] });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/2fa/disable", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+        let body = serde_json::json!({ "code": backup_codes[1] });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/2fa/disable", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        // and afterwards the password is enough again
+        common::login_test_user(&app, &username, &password).await;
+    }
+
+    // test that registering with a malformed username (too long or short, or with other characters) or an over-length email is rejected
+    #[tokio::test]
+    async fn test_register_rejects_overlong_fields() {
+        let mut state = common::setup_app_state().await;
+        // more attempts than the registration rate limit allows from one client
+        state.config.registration_rate_limit = 0;
+        state.registration_limiter = financetracker::RateLimiter::for_registration(&state.config);
+        let app = build_app(state);
+
+        for username in ["u".repeat(51), "u".to_string(), "no spaces allowed".to_string()] {
+            let mut body = unique_registration();
+            body["username"] = serde_json::json!(username);
+            let (status, _) = common::send_request(&app, "POST", "/api/users/register", None, Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        let mut body = unique_registration();
+        body["email"] = serde_json::json!(format!("{}@example.com", "e".repeat(250)));
+        let (status, _) = common::send_request(&app, "POST", "/api/users/register", None, Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    // test that the bootstrap endpoint returns every section, all scoped to the caller
+    #[tokio::test]
+    async fn test_bootstrap() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username1, password1) = common::create_and_register_test_user(&app).await;
+        let (user_id1, access_token1) = common::login_test_user(&app, &username1, &password1).await;
+        let (username2, password2) = common::create_and_register_test_user(&app).await;
+        let (user_id2, access_token2) = common::login_test_user(&app, &username2, &password2).await;
+
+        // both users spend in the current month, but only the first has a budget
+        let today = chrono::Utc::now().date_naive();
+        let month = today.format("%Y-%m-01").to_string();
+        let own = common::insert_test_transaction(&state, user_id1, "25.00", "expense", Some("Food"), &today.to_string(), None).await;
+        common::insert_test_transaction(&state, user_id2, "99.00", "expense", Some("Food"), &today.to_string(), None).await;
+
+        let budget = serde_json::json!({ "month": month, "category": "Food", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token1), Some(budget)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let (status, bootstrap) = common::send_request(&app, "GET", "/api/me/bootstrap", Some(&access_token1), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(bootstrap["profile"]["user_id"], user_id1.to_string());
+        assert_eq!(bootstrap["profile"]["username"], username1);
+        assert_eq!(bootstrap["profile"]["two_factor_enabled"], false);
+        assert_eq!(bootstrap["session"]["user_id"], user_id1.to_string());
+        assert_eq!(bootstrap["budgets"].as_array().unwrap().len(), 1);
+        assert_eq!(bootstrap["budget_progress"][0]["spent"], "25.00");
+        let ids: Vec<&str> = bootstrap["recent_transactions"].as_array().unwrap().iter().map(|t| t["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec![own.to_string()]);
+
+        // the second user sees only their own data
+        let (_, bootstrap) = common::send_request(&app, "GET", "/api/me/bootstrap", Some(&access_token2), None).await;
+        assert_eq!(bootstrap["profile"]["username"], username2);
+        assert_eq!(bootstrap["budgets"], serde_json::json!([]));
+        assert_eq!(bootstrap["recent_transactions"].as_array().unwrap().len(), 1);
+        assert_eq!(bootstrap["recent_transactions"][0]["amount"], "99.00");
+
+        // and it needs a token like every other route
+        let (status, _) = common::send_request(&app, "GET", "/api/me/bootstrap", None, None).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    // test that "today" follows the user's timezone rather than UTC, and that unknown timezones are rejected
+    #[tokio::test]
+    async fn test_user_timezone_sets_today() {
+        use chrono::Timelike;
+
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let body = serde_json::json!({ "timezone": "Mars/Olympus_Mons" });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        // at any moment one of these fixed-offset zones is on a different date from UTC
+        // (UTC+14 from 10:00 UTC onwards, UTC-12 before 12:00 UTC)
+        let now = chrono::Utc::now();
+        let (timezone, offset_hours) = if now.hour() >= 10 { ("Pacific/Kiritimati", 14) } else { ("Etc/GMT+12", -12) };
+        let local_today = (now + chrono::Duration::hours(offset_hours)).date_naive();
+        assert_ne!(local_today, now.date_naive());
+
+        let body = serde_json::json!({ "timezone": timezone });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        let (_, bootstrap) = common::send_request(&app, "GET", "/api/me/bootstrap", Some(&access_token), None).await;
+        assert_eq!(bootstrap["profile"]["timezone"], timezone);
+
+        // a duplicate with no date is dated the user's today, not UTC's
+        let original = common::insert_test_transaction(&state, user_id, "3.00", "expense", Some("Coffee"), "2026-01-05", None).await;
+        let (status, copy) = common::send_request(&app, "POST", &format!("/api/transactions/{}/duplicate", original), Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(copy["date"], local_today.to_string());
+    }
+
+    // test that notification preferences default to off, can be updated, and reject a send day past the 28th
+    #[tokio::test]
+    async fn test_notification_preferences() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let (_, bootstrap) = common::send_request(&app, "GET", "/api/me/bootstrap", Some(&access_token), None).await;
+        assert_eq!(bootstrap["profile"]["monthly_summary_enabled"], false);
+        assert_eq!(bootstrap["profile"]["budget_alerts_enabled"], false);
+        assert_eq!(bootstrap["profile"]["summary_send_day"], 1);
+
+        for day in [0, 29] {
+            let body = serde_json::json!({ "monthly_summary_enabled": true, "summary_send_day": day });
+            let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+            assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        // a bad day rejects the whole update, so the valid fields sent with it aren't applied either
+        let timezone = bootstrap["profile"]["timezone"].clone();
+        let body = serde_json::json!({ "username": format!("renamed_{}", &username[username.len() - 8..]), "timezone": "Pacific/Auckland", "summary_send_day": 40 });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        let (_, unchanged) = common::send_request(&app, "GET", "/api/me/bootstrap", Some(&access_token), None).await;
+        assert_eq!(unchanged["profile"]["username"], username);
+        assert_eq!(unchanged["profile"]["timezone"], timezone);
+        assert_eq!(unchanged["profile"]["monthly_summary_enabled"], false);
+
+        let body = serde_json::json!({ "monthly_summary_enabled": true, "summary_send_day": 28 });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        // fields left out keep their values
+        let body = serde_json::json!({ "budget_alerts_enabled": true });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::NO_CONTENT);
+
+        let (_, bootstrap) = common::send_request(&app, "GET", "/api/me/bootstrap", Some(&access_token), None).await;
+        assert_eq!(bootstrap["profile"]["monthly_summary_enabled"], true);
+        assert_eq!(bootstrap["profile"]["budget_alerts_enabled"], true);
+        assert_eq!(bootstrap["profile"]["summary_send_day"], 28);
+    }
+
+    // test that read-only mode blocks changes with a 403 while logging in and reading still work
+    #[tokio::test]
+    async fn test_read_only_mode() {
+        let mut state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+
+        state.config.read_only = true;
+        let app = build_app(state);
+        let (_user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        let (status, budgets) = common::send_request(&app, "GET", "/api/budgets", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(budgets, serde_json::json!([]));
+
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "Food", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+
+        let body = serde_json::json!({ "timezone": "UTC" });
+        let (status, _) = common::send_request(&app, "PATCH", "/api/users/me", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+    }
+
+    // test that resetting clears the user's data after a password check, while the account and token keep working
+    #[tokio::test]
+    async fn test_reset_user_data() {
+        let state = common::setup_app_state().await;
+        let app = build_app(state.clone());
+        let (username, password) = common::create_and_register_test_user(&app).await;
+        let (user_id, access_token) = common::login_test_user(&app, &username, &password).await;
+
+        common::insert_test_transaction(&state, user_id, "12.00", "expense", Some("Food"), "2026-01-05", None).await;
+        common::insert_test_transaction(&state, user_id, "2000.00", "income", Some("Salary"), "2026-01-01", None).await;
+        let body = serde_json::json!({ "month": "2026-01-01", "category": "Food", "amount": "100.00" });
+        let (status, _) = common::send_request(&app, "POST", "/api/budgets", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+
+        let body = serde_json::json!({ "password": "not-the-password" });
+        let (status, _) = common::send_request(&app, "POST", "/api/users/me/reset", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::UNAUTHORIZED);
+
+        let body = serde_json::json!({ "password": password });
+        let (status, cleared) = common::send_request(&app, "POST", "/api/users/me/reset", Some(&access_token), Some(body)).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(cleared, serde_json::json!({ "transactions": 2, "archived_transactions": 0, "budgets": 1 }));
+
+        // the same token still works, and there's nothing left
+        let (status, range) = common::send_request(&app, "GET", "/api/transactions/range", Some(&access_token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(range["count"], 0);
+        let (_, budgets) = common::send_request(&app, "GET", "/api/budgets", Some(&access_token), None).await;
+        assert_eq!(budgets, serde_json::json!([]));
+
+        // and so does logging in again
+        let (same_user, _) = common::login_test_user(&app, &username, &password).await;
+        assert_eq!(same_user, user_id);
+    }
+}